@@ -1,4 +1,5 @@
 use crate::{
+    movegen::Allmv,
     tables::{
         atk_by_type,
         sliding_piece::{between, bishop_atk, rook_atk},
@@ -17,6 +18,13 @@ impl Board {
 
     /// Whether a move puts the opponent in check on the current board.
     pub fn gives_check(&self, m: Move) -> bool {
+        let (direct, discovered) = self.check_info(m);
+        direct || discovered
+    }
+
+    /// Whether a move gives a direct check, and whether it gives a discovered check.
+    /// If both are true, the move gives double check.
+    pub fn check_info(&self, m: Move) -> (bool, bool) {
         let stm = self.stm;
         let opp = !self.stm;
 
@@ -31,47 +39,54 @@ impl Board {
 
         let pt = self.pc_at(src).pt();
 
-        // Direct check.
-        if !(self.king_line(pt) & dbb).is_empty() {
-            return true;
-        }
+        let direct = !(self.king_line(pt) & dbb).is_empty()
+            || match m.flag() {
+                // Castling.
+                // This can give check if the enemy king is aligned with the square the rook will move
+                // to, and there are no pieces between it and the enemy king.
+                MoveFlag::Castling => {
+                    let (_, rt) = self.castlingmask.rook_src_dst(dst);
+                    let line = between(opp_ksq, rt);
+                    !line.is_empty() && (line & occ).is_empty()
+                }
+
+                // Promotions.
+                // We just need to see if the piece we are promoting puts the king in check.
+                f if f.is_promo() => !(atk_by_type(f.get_promo(), dst, occ) & opp_kbb).is_empty(),
+
+                _ => false,
+            };
 
         // Discovered check.
         // If we are in line with the enemy king, check if there is a sliding piece giving check,
         // and that we have moved out of the way.
-        if (!(bishop_atk(opp_ksq, occ) & self.diag_bb(stm)).is_empty() || !(rook_atk(opp_ksq, occ) & self.orth_bb(stm)).is_empty())
-            && (between(opp_ksq, src) & between(opp_ksq, dst)).is_empty()
-        {
-            return true;
-        }
+        let discovered = (!(bishop_atk(opp_ksq, occ) & self.diag_bb(stm)).is_empty()
+            || !(rook_atk(opp_ksq, occ) & self.orth_bb(stm)).is_empty())
+            && (between(opp_ksq, src) & between(opp_ksq, dst)).is_empty();
 
-        match m.flag() {
-            // En passant.
-            // We have checked the normal en passant stuff,
-            // we just need to see if it leads to discovered check.
-            MoveFlag::EnPassant => {
-                let epsq = dst.forward(opp).bb();
-                let ep_occ = occ ^ epsq;
+        // En passant.
+        // The captured pawn also moves out of the way, which can discover a check.
+        let discovered = discovered
+            || m.flag() == MoveFlag::EnPassant && {
+                let ep_occ = occ ^ dst.forward(opp).bb();
                 !(bishop_atk(opp_ksq, ep_occ) & self.diag_bb(stm)).is_empty() || !(rook_atk(opp_ksq, ep_occ) & self.orth_bb(stm)).is_empty()
-            }
-
-            // Castling.
-            // This can give check if the enemy king is aligned with the square the rook will move
-            // to, and there are no pieces between it and the enemy king.
-            MoveFlag::Castling => {
-                let (_, rt) = self.castlingmask.rook_src_dst(dst);
-                let line = between(opp_ksq, rt);
-                !line.is_empty() && (line & occ).is_empty()
-            }
-
-            // Promotions.
-            // We have already checked the normal promotion stuff,
-            // we just need to see if the piece we are promoting puts the king in check.
-            f if f.is_promo() => !(atk_by_type(f.get_promo(), dst, occ) & opp_kbb).is_empty(),
-
-            // We have done all the checks for other move types already: they do not give check.
-            _ => false,
+            };
+
+        (direct, discovered)
+    }
+
+    /// Whether a move checkmates the opponent on the current board.
+    pub fn gives_checkmate(&mut self, m: Move) -> bool {
+        if !self.gives_check(m) {
+            return false;
         }
+
+        self.make_move(m);
+        let mut has_reply = false;
+        self.enumerate_moves::<_, Allmv>(|_| has_reply = true);
+        self.undo_move();
+
+        !has_reply
     }
 }
 
@@ -116,4 +131,36 @@ mod tests {
             "1n5r/1kq1p1b1/2p2n1p/1P3bp1/1Q6/2PPP2P/5P2/rNB1KB2 w - - 0 1", [("b5b6", false)];
         );
     }
+
+    #[test]
+    fn test_check_info() {
+        let b: Board = "4k3/8/8/8/4N3/8/8/K3R2Q w - - 0 1".parse().unwrap();
+
+        // Knight moves off the e-file: checks from the knight are double checks.
+        assert_eq!(b.check_info(b.find_move("e4f6").unwrap()), (true, true));
+        assert_eq!(b.check_info(b.find_move("e4d6").unwrap()), (true, true));
+        assert_eq!(b.check_info(b.find_move("e4c5").unwrap()), (false, true));
+
+        assert_eq!(b.check_info(b.find_move("h1h5").unwrap()), (true, false));
+        assert_eq!(b.check_info(b.find_move("a1b1").unwrap()), (false, false));
+
+        // Promoting out of the way of the rook.
+        let b: Board = "8/R2Pk3/8/8/8/8/8/K7 w - - 0 1".parse().unwrap();
+        assert_eq!(b.check_info(b.find_move("d7d8q").unwrap()), (true, true));
+        assert_eq!(b.check_info(b.find_move("d7d8n").unwrap()), (false, true));
+    }
+
+    #[test]
+    fn test_gives_checkmate() {
+        // Back rank mate.
+        let mut b: Board = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".parse().unwrap();
+        assert!(b.gives_checkmate(b.find_move("a1a8").unwrap()));
+        assert!(!b.gives_checkmate(b.find_move("a1a7").unwrap()));
+
+        // The king can escape to h7.
+        let mut b: Board = "6k1/5pp1/7p/8/8/8/8/R5K1 w - - 0 1".parse().unwrap();
+        let before = b.to_fen();
+        assert!(!b.gives_checkmate(b.find_move("a1a8").unwrap()));
+        assert_eq!(b.to_fen(), before);
+    }
 }
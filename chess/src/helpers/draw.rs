@@ -1,5 +1,25 @@
+use std::fmt;
+
 use crate::types::{bitboard::Bitboard, board::Board, color::Color, piece::Piece};
 
+/// The result of a game that has ended.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    Checkmate,
+    Stalemate,
+    Draw,
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Checkmate => write!(f, "checkmate"),
+            Self::Stalemate => write!(f, "stalemate"),
+            Self::Draw => write!(f, "draw"),
+        }
+    }
+}
+
 /// Draw implementations for board.
 impl Board {
     /// Whether the current position leads to a draw.
@@ -7,6 +27,29 @@ impl Board {
         self.is_fifty_move() || self.is_insufficient_material() || self.is_repetition(ply_from_null)
     }
 
+    /// Whether either side may claim a draw (50 move rule or threefold repetition).
+    pub fn is_claimable_draw(&self) -> bool {
+        self.is_fifty_move() || self.repetition_count() >= 3
+    }
+
+    /// Whether the game is drawn automatically, without needing a claim (75 move rule or fivefold repetition).
+    /// A checkmate on the move that reaches 75 moves takes precedence.
+    pub fn is_forced_draw(&self) -> bool {
+        (self.state.halfmoves >= 150 && (!self.in_check() || self.has_moves())) || self.repetition_count() >= 5
+    }
+
+    /// The result of the game if it has ended in this position.
+    /// Draws that must be claimed do not end the game.
+    pub fn game_result(&self) -> Option<GameResult> {
+        if !self.has_moves() {
+            Some(if self.in_check() { GameResult::Checkmate } else { GameResult::Stalemate })
+        } else if self.is_forced_draw() || self.is_insufficient_material() {
+            Some(GameResult::Draw)
+        } else {
+            None
+        }
+    }
+
     /// Whether the 50 move rule has been passed.
     const fn is_fifty_move(&self) -> bool {
         self.state.halfmoves >= 100
@@ -66,7 +109,7 @@ impl Board {
     }
 
     /// Whether the current position has been repeated.
-    fn is_repetition(&self, ply_from_null: usize) -> bool {
+    pub fn is_repetition(&self, ply_from_null: usize) -> bool {
         let end = 1 + ply_from_null.min(self.state.halfmoves);
 
         if end == 1 {
@@ -76,10 +119,17 @@ impl Board {
         let key = self.state.hash.key;
         self.history.iter().rev().take(end).skip(1).step_by(2).any(|s| s.hash.key == key)
     }
+
+    /// The number of times the current position has occurred in the game, including now.
+    fn repetition_count(&self) -> usize {
+        let key = self.state.hash.key;
+        1 + self.history.iter().rev().take(self.state.halfmoves).skip(1).step_by(2).filter(|s| s.hash.key == key).count()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::GameResult;
     use crate::types::{
         board::Board,
         moves::{Move, MoveFlag},
@@ -134,5 +184,56 @@ mod tests {
         assert_eq!(b.state.hash.key, h);
 
         assert!(b.is_repetition(9));
+        assert_eq!(b.repetition_count(), 4);
+        assert!(b.is_claimable_draw());
+        assert!(!b.is_forced_draw());
+    }
+
+    #[test]
+    fn test_fivefold_repetition() {
+        let mut b = Board::default();
+
+        for _ in 0..4 {
+            b.make_move(Move::new(Square::G1, Square::F3, MoveFlag::Normal));
+            b.make_move(Move::new(Square::G8, Square::F6, MoveFlag::Normal));
+            b.make_move(Move::new(Square::F3, Square::G1, MoveFlag::Normal));
+
+            assert!(!b.is_forced_draw());
+
+            b.make_move(Move::new(Square::F6, Square::G8, MoveFlag::Normal));
+        }
+
+        assert_eq!(b.repetition_count(), 5);
+        assert!(b.is_forced_draw());
+    }
+
+    #[test]
+    fn test_seventy_five_move() {
+        let b: Board = "8/8/8/r4k2/2K5/8/8/8 w - - 100 80".parse().unwrap();
+        assert!(b.is_claimable_draw());
+        assert!(!b.is_forced_draw());
+
+        let b: Board = "8/8/8/r4k2/2K5/8/8/8 w - - 149 80".parse().unwrap();
+        assert!(!b.is_forced_draw());
+
+        let b: Board = "8/8/8/r4k2/2K5/8/8/8 w - - 150 80".parse().unwrap();
+        assert!(b.is_forced_draw());
+
+        // Checkmate on the 75th move takes precedence.
+        let b: Board = "R6k/8/6K1/8/8/8/8/8 b - - 150 80".parse().unwrap();
+        assert!(!b.is_forced_draw());
+    }
+
+    #[test]
+    fn test_game_result() {
+        let result = |fen: &str| fen.parse::<Board>().unwrap().game_result();
+
+        assert_eq!(result("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"), Some(GameResult::Checkmate));
+        assert_eq!(result("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), Some(GameResult::Stalemate));
+        assert_eq!(result("7k/8/6K1/8/8/8/8/8 b - - 0 1"), Some(GameResult::Draw));
+        assert_eq!(result("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), None);
+
+        // The 50 move rule must be claimed.
+        assert_eq!(result("4k3/8/8/8/8/8/8/R3K3 w - - 100 80"), None);
     }
 }
@@ -1,6 +1,8 @@
 use crate::{
     movegen::Allmv,
+    tables::{atk_by_type, leaping_piece::pawn_atk},
     types::{
+        bitboard::Bitboard,
         board::Board,
         moves::{Move, MoveFlag},
         piece::{CPiece, Piece},
@@ -8,6 +10,64 @@ use crate::{
 };
 
 impl Board {
+    /// Whether the given move could be played in this position, ignoring king safety.
+    /// This is a cheap check for moves that may have come from a different position.
+    pub fn is_pseudo_legal(&self, m: Move) -> bool {
+        if m.is_none() {
+            return false;
+        }
+
+        let (src, dst) = (m.src(), m.dst());
+        let flag = m.flag();
+        let pc = self.pc_at(src);
+        let target = self.pc_at(dst);
+
+        // We can only move our own pieces.
+        if pc == CPiece::None || pc.color() != self.stm {
+            return false;
+        }
+
+        let pt = pc.pt();
+
+        // The king and rook squares depend on the castling rights, so leave the rest to full legality.
+        if flag == MoveFlag::Castling {
+            return pt == Piece::King;
+        }
+
+        if flag == MoveFlag::EnPassant {
+            return pt == Piece::Pawn && dst == self.state.epsq && pawn_atk(self.stm, src).has(dst);
+        }
+
+        // Captures must land on an enemy piece, and all other moves on an empty square.
+        let dst_ok = if flag.is_cap() {
+            target != CPiece::None && target.color() != self.stm
+        } else {
+            target == CPiece::None
+        };
+        if !dst_ok {
+            return false;
+        }
+
+        if pt != Piece::Pawn {
+            return matches!(flag, MoveFlag::Normal | MoveFlag::Capture) && atk_by_type(pt, src, self.occ()).has(dst);
+        }
+
+        // Pawns must promote if, and only if, they reach the last rank.
+        if flag.is_promo() != Bitboard::PR[self.stm.idx()].has(src) {
+            return false;
+        }
+
+        let push = src.forward(self.stm);
+
+        match flag {
+            MoveFlag::DoublePush => {
+                Bitboard::DP[self.stm.idx()].has(push) && self.pc_at(push) == CPiece::None && dst == push.forward(self.stm)
+            }
+            f if f.is_cap() => pawn_atk(self.stm, src).has(dst),
+            _ => dst == push,
+        }
+    }
+
     /// Whether the given move is legal in this position.
     /// TODO: Benchmark + optimize
     pub fn is_legal(&self, m: Move) -> bool {
@@ -15,7 +75,7 @@ impl Board {
         let src_piece = self.pc_at(m.src());
         let in_check = self.in_check();
 
-        if src_piece == CPiece::None {
+        if !self.is_pseudo_legal(m) {
             return false;
         }
 
@@ -54,3 +114,93 @@ impl Board {
         found
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{
+        board::Board,
+        moves::{Move, MoveFlag},
+        square::Square,
+    };
+
+    const FLAGS: [MoveFlag; 13] = [
+        MoveFlag::Normal,
+        MoveFlag::DoublePush,
+        MoveFlag::Castling,
+        MoveFlag::Capture,
+        MoveFlag::EnPassant,
+        MoveFlag::PromoN,
+        MoveFlag::PromoB,
+        MoveFlag::PromoR,
+        MoveFlag::PromoQ,
+        MoveFlag::CPromoN,
+        MoveFlag::CPromoB,
+        MoveFlag::CPromoR,
+        MoveFlag::CPromoQ,
+    ];
+
+    #[test]
+    fn test_pseudo_legal_flags() {
+        let b: Board = "r3k2r/pP1p2pp/8/2pP4/8/8/4P3/R3K2R w KQkq c6 0 1".parse().unwrap();
+        let m = |src, dst, flag| b.is_pseudo_legal(Move::new(src, dst, flag));
+
+        assert!(m(Square::E2, Square::E3, MoveFlag::Normal));
+        assert!(!m(Square::E2, Square::E4, MoveFlag::Normal));
+
+        // Double pushes only from the starting rank.
+        assert!(m(Square::E2, Square::E4, MoveFlag::DoublePush));
+        assert!(!m(Square::D5, Square::D7, MoveFlag::DoublePush));
+
+        // Captures need an enemy piece on the destination.
+        assert!(m(Square::B7, Square::A8, MoveFlag::CPromoQ));
+        assert!(!m(Square::A1, Square::A7, MoveFlag::Normal));
+        assert!(!m(Square::A1, Square::E1, MoveFlag::Capture));
+        assert!(!m(Square::A1, Square::A5, MoveFlag::Capture));
+
+        // En passant only onto the en passant square.
+        assert!(m(Square::D5, Square::C6, MoveFlag::EnPassant));
+        assert!(!m(Square::D5, Square::E6, MoveFlag::EnPassant));
+
+        // Pawns promote on the last rank only.
+        assert!(m(Square::B7, Square::B8, MoveFlag::PromoN));
+        assert!(!m(Square::B7, Square::B8, MoveFlag::Normal));
+        assert!(!m(Square::E2, Square::E3, MoveFlag::PromoQ));
+
+        // Only pawns can use pawn flags, and only kings can castle.
+        assert!(!m(Square::A1, Square::A3, MoveFlag::DoublePush));
+        assert!(!m(Square::A1, Square::A8, MoveFlag::CPromoQ));
+        assert!(m(Square::E1, Square::G1, MoveFlag::Castling));
+        assert!(!m(Square::H1, Square::F1, MoveFlag::Castling));
+
+        // We can't move the opponent's pieces.
+        assert!(!m(Square::E8, Square::E7, MoveFlag::Normal));
+        assert!(!Board::default().is_pseudo_legal(Move::NONE));
+    }
+
+    #[test]
+    fn test_legal_matches_movegen() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkb1r/pp1p1pPp/8/2p1pP2/1P1P4/3P3P/P1P1P3/RNBQKBNR w KQkq e6 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ] {
+            let b: Board = fen.parse().unwrap();
+            let moves = b.gen_moves();
+
+            for src in Square::iter() {
+                for dst in Square::iter() {
+                    for flag in FLAGS {
+                        let m = Move::new(src, dst, flag);
+                        assert_eq!(b.is_legal(m), moves.contains(&m), "{fen}: {m:?}");
+
+                        if moves.contains(&m) {
+                            assert!(b.is_pseudo_legal(m), "{fen}: {m:?}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
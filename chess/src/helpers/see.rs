@@ -62,8 +62,10 @@ impl Board {
         }
 
         // Setup sliders.
-        let diag_sliders = self.all_diag();
-        let orth_sliders = self.all_orth();
+        // Only sliders on a line through the target square can ever attack it, so we find these
+        // once up front. Removing pieces can only reveal more of them.
+        let diag_sliders = self.all_diag() & bishop_atk(dst, Bitboard::EMPTY);
+        let orth_sliders = self.all_orth() & rook_atk(dst, Bitboard::EMPTY);
 
         // Setup pins.
         let mut diag_pinned = [Bitboard::EMPTY; Color::NUM];
@@ -115,11 +117,12 @@ impl Board {
             let (p, s) = self.get_lva(stm, own_atk);
             occ.pop(s);
 
+            // Look for newly revealed sliders, if there are any left hidden on that line.
             let pt = p.pt();
-            if matches!(pt, Piece::Queen | Piece::Bishop | Piece::Pawn) {
+            if matches!(pt, Piece::Queen | Piece::Bishop | Piece::Pawn) && !(diag_sliders & occ & !atk).is_empty() {
                 atk |= bishop_atk(dst, occ) & diag_sliders;
             }
-            if matches!(pt, Piece::Queen | Piece::Rook) {
+            if matches!(pt, Piece::Queen | Piece::Rook) && !(orth_sliders & occ & !atk).is_empty() {
                 atk |= rook_atk(dst, occ) & orth_sliders;
             }
 
@@ -188,6 +191,15 @@ const SEE_TESTS: &[(&str, &str, i32, bool)] = &[
     ("2k5/3n2b1/2nq4/4R3/5P2/3N1N2/8/5K2 b - - 0 1", "d6e5", R - Q + P, true),
     ("5r1k/3b1q1p/1npb4/1p6/pPpP1N2/2P4B/2NBQ1P1/5R1K b - - 0 1", "d6f4", 0, false),
     ("5r1k/3b1q1p/1npb4/1p6/pPpP1N2/2P4B/2NBQ1P1/5R1K b - - 0 1", "d6f4", -P, true),
+    ("5k2/2P5/8/8/8/8/8/5K2 w - - 0 1", "c7c8q", Q - P, true),
+    ("5k2/2P5/8/8/8/8/8/5K2 w - - 0 1", "c7c8q", Q - P + 1, false),
+    ("5k2/2P5/8/8/8/8/8/5K2 w - - 0 1", "c7c8n", N - P, true),
+    ("5k2/2P5/4b3/8/8/8/8/3R1K2 w - - 0 1", "c7c8q", -P, true),
+    ("5k2/2P5/4b3/8/8/8/8/3R1K2 w - - 0 1", "c7c8q", -P + 1, false),
+    ("5k2/2P5/4b3/8/8/8/8/3R1K2 w - - 0 1", "c7c8n", -P, true),
+    ("5k2/2P5/4b3/8/8/8/8/3R1K2 w - - 0 1", "c7c8n", -P + 1, false),
+    ("5k2/2P5/4b3/8/8/8/8/2R2K2 w - - 0 1", "c7c8q", B - P, true),
+    ("5k2/2P5/4b3/8/8/8/8/2R2K2 w - - 0 1", "c7c8q", B - P + 1, false),
 ];
 
 /// Benchmark the static exchange eval.
@@ -321,7 +321,12 @@ impl Board {
 mod tests {
     use crate::{
         movegen::{Noisy, Quiet},
-        types::board::Board,
+        types::{
+            board::Board,
+            color::Color,
+            piece::{CPiece, Piece},
+            square::Square,
+        },
     };
 
     #[test]
@@ -348,4 +353,82 @@ mod tests {
             });
         }
     }
+
+    #[test]
+    fn test_frc_castling_canonical_dst() {
+        // King far from the e-file, with both castling sides available.
+        const POSITIONS: &[(&str, Square)] = &[
+            ("rk5r/pppppppp/8/8/8/8/PPPPPPPP/RK5R w HAha - 0 1", Square::B1),
+            ("rk5r/pppppppp/8/8/8/8/PPPPPPPP/RK5R b HAha - 0 1", Square::B8),
+            ("r5kr/pppppppp/8/8/8/8/PPPPPPPP/R5KR w HAha - 0 1", Square::G1),
+            ("r5kr/pppppppp/8/8/8/8/PPPPPPPP/R5KR b HAha - 0 1", Square::G8),
+        ];
+
+        for &(fen, ksq) in POSITIONS {
+            let b: Board = fen.parse().unwrap();
+
+            let mut castles = Vec::new();
+            b.enumerate_castling(&mut |m| castles.push(m));
+            assert_eq!(castles.len(), 2, "{fen}");
+
+            for m in castles {
+                let (ks_dst, qs_dst) = (Square::G1.relative(b.stm), Square::C1.relative(b.stm));
+                assert_eq!(m.src(), ksq);
+                assert!(m.dst() == ks_dst || m.dst() == qs_dst, "{fen}: non-canonical castling destination {}", m.dst());
+
+                // Rook should come from the correct side, and land next to the king.
+                let (rf, rt) = b.castlingmask.rook_src_dst(m.dst());
+                assert_eq!(b.pc_at(rf), CPiece::make(b.stm, Piece::Rook));
+                assert_eq!(rt, if m.dst() == ks_dst { Square::F1 } else { Square::D1 }.relative(b.stm));
+                assert_eq!(rf.file() > ksq.file(), m.dst() == ks_dst);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frc_castling_adjacent_files() {
+        // Castling should be generated iff the king and rook paths are clear and the king path is safe.
+        #[rustfmt::skip]
+        const POSITIONS: &[(&str, &[&str])] = &[
+            // King already on its destination.
+            ("4k3/8/8/8/8/8/8/6KR w H - 0 1", &["g1h1"]),
+            ("5r2/4k3/8/8/8/8/8/6KR w H - 0 1", &["g1h1"]),
+            ("4k3/8/8/8/8/8/8/R1K5 w A - 0 1", &["c1a1"]),
+            ("4k3/8/8/8/8/8/8/RNK5 w A - 0 1", &[]),
+
+            // Rook already on its destination, or adjacent to the king.
+            ("4k3/8/8/8/8/8/8/5KR1 w G - 0 1", &["f1g1"]),
+            ("4k3/8/8/8/8/8/8/5KRr w G - 0 1", &[]),
+            ("4k3/8/8/8/8/8/8/RK6 w A - 0 1", &["b1a1"]),
+            ("4k3/8/8/8/8/8/8/RK1N4 w A - 0 1", &[]),
+            ("3r4/4k3/8/8/8/8/8/RK6 w A - 0 1", &["b1a1"]),
+            ("2r5/4k3/8/8/8/8/8/RK6 w A - 0 1", &[]),
+            ("4k3/8/8/8/8/8/8/rRK5 w B - 0 1", &[]),
+            ("4k3/8/8/8/8/8/8/3KR3 w E - 0 1", &["d1e1"]),
+            ("4r3/3k4/8/8/8/8/8/3KR3 w E - 0 1", &[]),
+
+            // Rook between the king and its destination.
+            ("4k3/8/8/8/8/8/8/1K1R4 w D - 0 1", &["b1d1"]),
+            ("4k3/8/8/8/8/8/8/1K1RN3 w D - 0 1", &[]),
+            ("4k3/8/8/8/8/8/8/1K1R3r w D - 0 1", &[]),
+        ];
+
+        for &(fen, expected) in POSITIONS {
+            let b: Board = fen.parse().unwrap();
+
+            let mut castles = Vec::new();
+            b.enumerate_castling(&mut |m| castles.push(m.to_uci(&b.castlingmask)));
+            assert_eq!(castles, expected, "{fen}");
+
+            // Castling should leave the king and rook on their canonical squares.
+            for m in castles.iter().filter_map(|s| b.find_move(s)) {
+                let mut after = b.clone();
+                let (_, rt) = b.castlingmask.rook_src_dst(m.dst());
+                after.make_move(m);
+                assert_eq!(after.pc_at(m.dst()), CPiece::make(Color::White, Piece::King), "{fen}");
+                assert_eq!(after.pc_at(rt), CPiece::make(Color::White, Piece::Rook), "{fen}");
+                assert_eq!(after.occ().nbits(), b.occ().nbits(), "{fen}");
+            }
+        }
+    }
 }
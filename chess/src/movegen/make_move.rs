@@ -3,6 +3,7 @@ use crate::types::{
     dirtypiece::DirtyPieces,
     moves::{Move, MoveFlag},
     piece::{CPiece, Piece},
+    square::Square,
 };
 
 /// Make and unmake move functions.
@@ -59,10 +60,9 @@ impl Board {
             }
 
             // Double push: update epsq.
+            // This is only kept, and hashed, once we know the capture is possible.
             MoveFlag::DoublePush => {
-                let epsq = src.forward(self.stm);
-                state.epsq = epsq;
-                state.hash.toggle_ep(epsq);
+                state.epsq = src.forward(self.stm);
                 state.halfmoves = 0;
                 DirtyPieces::Add1Sub1((pc, dst), (pc, src))
             }
@@ -73,6 +73,7 @@ impl Board {
                 state.cap = cap;
                 self.pop_piece(dst);
                 state.hash.toggle_piece(cap, dst);
+                state.hash.toggle_material(cap, self.piece_count(cap));
                 state.halfmoves = 0;
                 DirtyPieces::Add1Sub2((pc, dst), (cap, dst), (pc, src))
             }
@@ -84,6 +85,7 @@ impl Board {
                 state.cap = cap;
                 self.pop_piece(epsq);
                 state.hash.toggle_piece(cap, epsq);
+                state.hash.toggle_material(cap, self.piece_count(cap));
                 state.halfmoves = 0;
                 DirtyPieces::Add1Sub2((pc, dst), (cap, epsq), (pc, src))
             }
@@ -92,6 +94,8 @@ impl Board {
             MoveFlag::PromoN | MoveFlag::PromoB | MoveFlag::PromoR | MoveFlag::PromoQ => {
                 let stm_pawn = CPiece::make(self.stm, Piece::Pawn);
                 pc = CPiece::make(self.stm, flag.get_promo());
+                state.hash.toggle_material(stm_pawn, self.piece_count(stm_pawn));
+                state.hash.toggle_material(pc, self.piece_count(pc));
                 state.halfmoves = 0;
                 DirtyPieces::Add1Sub1((pc, dst), (stm_pawn, src))
             }
@@ -103,7 +107,10 @@ impl Board {
                 state.cap = cap;
                 self.pop_piece(dst);
                 state.hash.toggle_piece(cap, dst);
+                state.hash.toggle_material(cap, self.piece_count(cap));
                 pc = CPiece::make(self.stm, flag.get_promo());
+                state.hash.toggle_material(stm_pawn, self.piece_count(stm_pawn));
+                state.hash.toggle_material(pc, self.piece_count(pc));
                 state.halfmoves = 0;
                 DirtyPieces::Add1Sub2((pc, dst), (cap, dst), (stm_pawn, src))
             }
@@ -129,6 +136,8 @@ impl Board {
         let old_state = std::mem::replace(&mut self.state, state);
         self.history.push(old_state);
 
+        self.settle_epsq();
+
         dp
     }
 
@@ -199,6 +208,7 @@ impl Board {
 
         state.hash = self.state.hash;
         state.castling = self.state.castling;
+        state.fullmoves = self.state.fullmoves + self.stm.idx();
 
         // Unset ep square from hash.
         state.hash.toggle_ep(self.state.epsq);
@@ -227,6 +237,29 @@ impl Board {
         self.state = self.history.pop().unwrap();
         self.stm = !self.stm;
     }
+
+    /// Get a copy of this board with the other side to move, as if a null move had been played.
+    /// Unlike [`Board::make_null`], this does not push to the history, so cannot be undone.
+    /// Returns None if the side to move is in check, as the result would not be a legal position.
+    pub fn with_side_to_move_flipped(&self) -> Option<Self> {
+        if self.in_check() {
+            return None;
+        }
+
+        let mut b = self.clone();
+        let mut state = self.state.clone();
+
+        state.hash.toggle_ep(state.epsq);
+        state.epsq = Square::Invalid;
+
+        b.stm = !b.stm;
+        state.hash.toggle_color();
+
+        b.update_masks(&mut state);
+        b.state = state;
+
+        Some(b)
+    }
 }
 
 #[cfg(test)]
@@ -274,6 +307,27 @@ mod tests {
         assert_eq!(b.state.epsq, Square::G3);
     }
 
+    #[test]
+    fn test_double_push_hash_matches_fen() {
+        let after = |fen: &str, uci: &str| {
+            let mut b: Board = fen.parse().unwrap();
+            b.make_move(b.find_move(uci).unwrap());
+            b
+        };
+
+        // No black pawn can capture on e3, so there is no en passant square.
+        let b = after("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4");
+        let x: Board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".parse().unwrap();
+        assert_eq!(b.state.epsq, Square::Invalid);
+        assert_eq!(b.state.hash, x.state.hash);
+
+        // The d4 pawn can capture on e3.
+        let b = after("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 3", "e2e4");
+        let x: Board = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3".parse().unwrap();
+        assert_eq!(b.state.epsq, Square::E3);
+        assert_eq!(b.state.hash, x.state.hash);
+    }
+
     #[test]
     fn test_move_castle() {
         let mut b: Board = "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4".parse().unwrap();
@@ -387,20 +441,42 @@ mod tests {
 
     #[test]
     fn test_null_move_ep() {
-        let mut b: Board = "rnbqkbnr/pp2pp1p/8/2pP2p1/8/2P5/PP1P1PPP/RNBQKBNR w KQkq g6 1 4".parse().unwrap();
+        let mut b: Board = "rnbqkbnr/pp2pp1p/8/2pP2pP/8/2P5/PP1P1PP1/RNBQKBNR w KQkq g6 0 4".parse().unwrap();
         b.make_null();
         assert_eq!(b.state.epsq, Square::Invalid);
     }
 
+    #[test]
+    fn test_side_to_move_flipped() {
+        let b: Board = "rnbqkbnr/pp2pp1p/8/2pP2pP/8/2P5/PP1P1PP1/RNBQKBNR w KQkq g6 0 4".parse().unwrap();
+        let f = b.with_side_to_move_flipped().unwrap();
+        assert_eq!(f.stm, !b.stm);
+        assert_eq!(f.state.epsq, Square::Invalid);
+        assert_eq!(f.history.len(), b.history.len());
+
+        let x: Board = "rnbqkbnr/pp2pp1p/8/2pP2pP/8/2P5/PP1P1PP1/RNBQKBNR b KQkq - 0 4".parse().unwrap();
+        assert_eq!(f.to_fen(), x.to_fen());
+        assert_eq!(f.state.hash, x.state.hash);
+        assert_eq!(f.gen_moves().len(), x.gen_moves().len());
+
+        // Flipping back gets the original position, without the en passant square.
+        let ff = f.with_side_to_move_flipped().unwrap();
+        assert_eq!(ff.to_fen(), "rnbqkbnr/pp2pp1p/8/2pP2pP/8/2P5/PP1P1PP1/RNBQKBNR w KQkq - 0 4");
+
+        // Can't pass while in check.
+        let c: Board = "4k3/8/8/8/8/8/4R3/4K3 b - - 0 1".parse().unwrap();
+        assert!(c.with_side_to_move_flipped().is_none());
+    }
+
     #[test]
     fn test_null_move_undo() {
-        let mut b: Board = "rnbqkbnr/pp2pp1p/8/2pP2p1/8/2P5/PP1P1PPP/RNBQKBNR w KQkq g6 1 4".parse().unwrap();
+        let mut b: Board = "rnbqkbnr/pp2pp1p/8/2pP2pP/8/2P5/PP1P1PP1/RNBQKBNR w KQkq g6 0 4".parse().unwrap();
         b.make_null();
         b.undo_null();
 
-        assert_eq!(b.to_fen(), "rnbqkbnr/pp2pp1p/8/2pP2p1/8/2P5/PP1P1PPP/RNBQKBNR w KQkq g6 1 4");
+        assert_eq!(b.to_fen(), "rnbqkbnr/pp2pp1p/8/2pP2pP/8/2P5/PP1P1PP1/RNBQKBNR w KQkq g6 0 4");
 
-        // let d: Board = "rnbqkbnr/pp2pp1p/8/2pP2p1/8/2P5/PP1P1PPP/RNBQKBNR w KQkq g6 1 4".parse().unwrap();
+        // let d: Board = "rnbqkbnr/pp2pp1p/8/2pP2pP/8/2P5/PP1P1PP1/RNBQKBNR w KQkq g6 0 4".parse().unwrap();
         // assert_eq!(b.castlingmask.mask, d.castlingmask.mask);
         // assert_eq!(b.castlingmask.mask, d.castlingmask.mask);
     }
@@ -5,6 +5,7 @@ use crate::{defs::MAX_MOVES, types::moves::Move};
 pub mod enumerate;
 pub mod make_move;
 pub mod perft;
+pub mod stats;
 pub mod update;
 
 pub type MoveList = ArrayVec<Move, MAX_MOVES>;
@@ -36,3 +37,27 @@ impl MgType for Allmv {
     const NOISY: bool = true;
     const QUIET: bool = true;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        movegen::Quiet,
+        types::{board::Board, moves::MoveFlag},
+    };
+
+    #[test]
+    fn test_max_moves_fit() {
+        // The most legal moves known to be possible in a reachable position.
+        let b: Board = "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1".parse().unwrap();
+        let moves = b.gen_moves();
+        assert_eq!(moves.len(), 218);
+        assert!(moves.len() <= MAX_MOVES);
+
+        // Almost all of them are quiet, and a list of tried quiets must be able to hold them.
+        let mut quiets = MoveList::new();
+        b.enumerate_moves::<_, Quiet>(|m| quiets.push(m));
+        assert_eq!(quiets.len(), 209);
+        assert!(quiets.iter().all(|m| m.flag() == MoveFlag::Normal));
+    }
+}
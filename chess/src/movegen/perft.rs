@@ -1,8 +1,18 @@
-use crate::types::board::Board;
+use crate::{
+    movegen::Allmv,
+    types::{board::Board, moves::Move},
+};
 
 impl Board {
     /// Counts all the legal positions up to a given depth.
+    /// At the last ply, the moves are counted without being made or stored (bulk counting).
     pub fn perft<const PRINT: bool>(&mut self, depth: usize) -> usize {
+        if depth <= 1 && !PRINT {
+            let mut n = 0;
+            self.enumerate_moves::<_, Allmv>(|_| n += 1);
+            return n;
+        }
+
         let mut total = 0;
 
         let mvs = self.gen_moves();
@@ -25,12 +35,81 @@ impl Board {
 
         total
     }
+
+    /// Walks all the legal moves up to a given depth, calling `f` with the board and move at each leaf.
+    pub fn perft_cb<F: FnMut(&Self, Move)>(&mut self, depth: usize, f: &mut F) {
+        if depth == 0 {
+            return;
+        }
+
+        if depth == 1 {
+            self.enumerate_moves::<_, Allmv>(|m| f(self, m));
+            return;
+        }
+
+        for m in self.gen_moves() {
+            self.make_move(m);
+            self.perft_cb(depth - 1, f);
+            self.undo_move();
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::types::board::Board;
 
+    #[test]
+    fn test_perft_cb_visits_leaves() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "1rkr4/8/8/8/8/8/8/1RKR4 w KQkq - 0 1",
+        ] {
+            let mut board: Board = fen.parse().unwrap();
+
+            for depth in 1..=3 {
+                let mut leaves = 0;
+                board.perft_cb(depth, &mut |b, m| {
+                    assert!(b.is_legal(m));
+                    leaves += 1;
+                });
+
+                assert_eq!(leaves, board.perft::<false>(depth), "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_bulk_counting_exact() {
+        // Reference perft that makes every move, including at the leaves.
+        fn perft_full(b: &mut Board, depth: usize) -> usize {
+            if depth == 0 {
+                return 1;
+            }
+
+            let mut total = 0;
+            for m in b.gen_moves() {
+                b.make_move(m);
+                total += perft_full(b, depth - 1);
+                b.undo_move();
+            }
+            total
+        }
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "1rkr4/8/8/8/8/8/8/1RKR4 w KQkq - 0 1",
+        ] {
+            let mut board: Board = fen.parse().unwrap();
+            for depth in 1..=3 {
+                assert_eq!(board.perft::<false>(depth), perft_full(&mut board, depth), "{fen}");
+                assert_eq!(board.perft::<true>(depth), perft_full(&mut board, depth), "{fen}");
+            }
+        }
+    }
+
     #[test]
     fn test_perft() {
         #[rustfmt::skip]
@@ -48,6 +127,14 @@ mod tests {
             ("5k2/8/8/8/4pP2/8/8/5RK1 b Q f3 0 9", 1050689, 6),
             ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 11030083, 6),
 
+            // Positions 2, 4, 5 and 6 from <https://www.chessprogramming.org/Perft_Results>.
+            // These are too slow to run at full depth without `full_tests`.
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 4085603, 4),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 422333, 4),
+            ("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", 422333, 4),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 2103487, 4),
+            ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 3894594, 4),
+
             // FRC starting positions.
             // See: http://www.open-aurec.com/wbforum/viewtopic.php?t=1404
             ("rbbknnqr/pppppppp/8/8/8/8/PPPPPPPP/RBBKNNQR w KQkq - 0 1", 124381396, 6),
@@ -63,6 +150,13 @@ mod tests {
             ("2k5/8/8/8/b7/8/8/2K3R1 w - - 0 1", 6578528, 6),
             ("3k4/8/8/8/8/8/8/rRK5 w - - 0 1", 3191684, 6),
             ("1rkr4/8/8/8/8/8/8/1RKR4 w KQkq - 0 1", 66969143, 6),
+
+            // FRC middlegame positions.
+            ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 8146062, 5),
+            ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", 16253601, 5),
+            ("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", 6417013, 5),
+            ("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", 9183776, 5),
+            ("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", 34030312, 5),
         ];
 
         #[rustfmt::skip]
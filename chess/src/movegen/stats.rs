@@ -0,0 +1,55 @@
+use crate::{
+    movegen::Allmv,
+    types::{board::Board, moves::MoveFlag},
+};
+
+/// Number of legal moves in a position, split by category.
+///
+/// Captures include en passant and capture promotions, and promotions include capture promotions.
+/// Quiets are all moves that neither capture nor promote, including castling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MovegenStats {
+    pub captures: usize,
+    pub quiets: usize,
+    pub promos: usize,
+    pub castles: usize,
+    pub en_passants: usize,
+}
+
+impl Board {
+    /// Count the legal moves in this position by category.
+    pub fn movegen_stats(&self) -> MovegenStats {
+        let mut stats = MovegenStats::default();
+
+        self.enumerate_moves::<_, Allmv>(|m| {
+            let flag = m.flag();
+
+            stats.captures += usize::from(flag.is_cap());
+            stats.quiets += usize::from(flag.is_quiet());
+            stats.promos += usize::from(flag.is_promo());
+            stats.castles += usize::from(flag == MoveFlag::Castling);
+            stats.en_passants += usize::from(flag == MoveFlag::EnPassant);
+        });
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_movegen_stats() {
+        // Kiwipete.
+        let b: Board = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        let stats = MovegenStats { captures: 8, quiets: 40, promos: 0, castles: 2, en_passants: 0 };
+        assert_eq!(b.movegen_stats(), stats);
+
+        // Promotions, en passant and castling.
+        let b: Board = "4k3/1P6/8/3pP3/8/8/8/R3K3 w Q d6 0 1".parse().unwrap();
+        let stats = MovegenStats { captures: 1, quiets: 17, promos: 4, castles: 1, en_passants: 1 };
+        assert_eq!(b.movegen_stats(), stats);
+        assert_eq!(stats.captures + stats.quiets + stats.promos, b.gen_moves().len());
+    }
+}
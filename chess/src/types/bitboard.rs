@@ -89,25 +89,22 @@ impl std::ops::Not for Bitboard {
     }
 }
 
-/// Print out a bitboard in a readable way.
+/// Print out a bitboard in a readable way, in the same layout as the board.
 impl fmt::Display for Bitboard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut output = String::new();
         for rank in (0..8).rev() {
-            output.push_str(&(rank + 1).to_string());
-            output.push(' ');
+            output.push_str(format!(" {} | ", rank + 1).as_str());
 
             for file in 0..8 {
-                let square = rank * 8 + file;
-                let bit = (self.0 >> square) & 1;
-                let symbol = if bit == 1 { "X" } else { "." };
-                output.push_str(symbol);
+                let symbol = if (self.0 >> (rank * 8 + file)) & 1 == 1 { 'X' } else { '.' };
+                output.push(symbol);
                 output.push(' ');
             }
 
             output.push('\n');
         }
-        output.push_str("  a b c d e f g h\n");
+        output.push_str("   +----------------\n     a b c d e f g h\n");
 
         write!(f, "{output}")
     }
@@ -134,8 +131,15 @@ impl Iterator for BitboardIterator {
             Some(sq)
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.0.nbits() as usize;
+        (n, Some(n))
+    }
 }
 
+impl ExactSizeIterator for BitboardIterator {}
+
 // Macro to help with debugging bitboards.
 #[macro_export]
 macro_rules! assert_bitboard_eq {
@@ -153,3 +157,46 @@ macro_rules! assert_bitboard_eq {
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iter_lsb_first() {
+        let bb = Square::H8.bb() | Square::A1.bb() | Square::E4.bb() | Square::C1.bb();
+        let squares: Vec<Square> = bb.into_iter().collect();
+        assert_eq!(squares, vec![Square::A1, Square::C1, Square::E4, Square::H8]);
+    }
+
+    #[test]
+    fn test_iter_count() {
+        for bb in [Bitboard::EMPTY, Bitboard::FULL, Bitboard::WHITE_SQ, Rank::R2.bb(), Bitboard(0x8000_0000_0000_0001)] {
+            assert_eq!(bb.into_iter().count(), bb.nbits() as usize);
+            assert_eq!(bb.into_iter().len(), bb.nbits() as usize);
+            assert!(bb.into_iter().all(|sq| bb.has(sq)));
+        }
+
+        let light_on_r2 = Rank::R2.bb().into_iter().filter(|&sq| Bitboard::WHITE_SQ.has(sq)).count();
+        assert_eq!(light_on_r2, 4);
+    }
+
+    #[test]
+    fn test_display() {
+        let bb = Square::A1.bb() | Square::E4.bb() | Square::H8.bb() | Rank::R7.bb();
+        let expected = concat!(
+            " 8 | . . . . . . . X \n",
+            " 7 | X X X X X X X X \n",
+            " 6 | . . . . . . . . \n",
+            " 5 | . . . . . . . . \n",
+            " 4 | . . . . X . . . \n",
+            " 3 | . . . . . . . . \n",
+            " 2 | . . . . . . . . \n",
+            " 1 | X . . . . . . . \n",
+            "   +----------------\n",
+            "     a b c d e f g h\n",
+        );
+        assert_eq!(bb.to_string(), expected);
+        assert_ne!(format!("{bb:?}"), expected);
+    }
+}
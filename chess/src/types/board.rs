@@ -2,9 +2,10 @@ use core::fmt;
 use std::str::FromStr;
 
 use crate::{
-    movegen::{Allmv, MoveList},
+    defs::MAX_PLY,
+    movegen::{Allmv, MoveList, Noisy},
     tables::{
-        leaping_piece::{all_pawn_atk, king_atk, knight_atk},
+        leaping_piece::{all_pawn_atk, king_atk, knight_atk, pawn_atk},
         sliding_piece::{bishop_atk, rook_atk},
     },
     types::{
@@ -19,6 +20,9 @@ use crate::{
     },
 };
 
+/// The largest halfmove clock a legal game can reach, as it is drawn by the 75 move rule at this point.
+const MAX_HALFMOVES: usize = 150;
+
 /// Board State struct.
 /// Contains information about the current board used to generate, make and unmake moves.
 #[derive(Default, Debug, Clone)]
@@ -76,9 +80,15 @@ impl Board {
             castlingmask: CastlingMask::default(),
 
             state: BoardState::default(),
-            history: Vec::new(),
+            history: Vec::with_capacity(MAX_PLY),
         }
     }
+
+    /// Make sure the history can hold a full search from this position without reallocating.
+    /// Cloning a board only keeps as much history capacity as it is using, so call this on clones too.
+    pub fn reserve_history(&mut self) {
+        self.history.reserve(MAX_PLY);
+    }
 }
 
 /// Default: Set to start position.
@@ -112,7 +122,44 @@ impl FromStr for Board {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_fen(s, false)
+    }
+}
+
+impl Board {
+    /// Parse a FEN, repairing move counters that are out of range instead of rejecting them.
+    /// The halfmove clock is clamped to at most 150, and the fullmove number to at least 1.
+    pub fn from_fen_lenient(s: &str) -> Result<Self, &'static str> {
+        Self::parse_fen(s, true)
+    }
+
+    /// Parse a FEN, additionally rejecting material that cannot arise from a legal game.
+    /// This rejects more than 8 pawns per side, pawns on the first or last rank, and more than 32 pieces.
+    pub fn from_fen_strict(s: &str) -> Result<Self, &'static str> {
+        let board: Self = s.parse()?;
+
+        if Color::iter().any(|c| board.pc_bb(c, Piece::Pawn).nbits() > 8) {
+            return Err("Too many pawns!");
+        }
+
+        if !(board.p_bb(Piece::Pawn) & (Rank::R1.bb() | Rank::R8.bb())).is_empty() {
+            return Err("Pawn on back rank!");
+        }
+
+        if board.occ().nbits() > 32 {
+            return Err("Too many pieces!");
+        }
+
+        Ok(board)
+    }
+
+    /// Parse a FEN, optionally repairing invalid move counters.
+    fn parse_fen(s: &str, lenient: bool) -> Result<Self, &'static str> {
         let fen = s.split_whitespace().take(6).collect::<Vec<&str>>();
+        if fen.len() < 6 {
+            return Err("Incomplete FEN!");
+        }
+
         let mut board = Self::empty();
 
         let mut state = BoardState::default();
@@ -173,28 +220,73 @@ impl FromStr for Board {
         state.castling = c_rights;
         state.hash.toggle_castling(c_rights);
 
-        match fen[3] {
-            "-" => state.epsq = Square::Invalid,
-            s => {
-                let epsq: Square = s.parse()?;
-                state.epsq = epsq;
-                state.hash.toggle_ep(epsq);
-            }
-        }
+        // Only keep the en passant square if it can actually be captured on.
+        state.epsq = match fen[3] {
+            "-" => Square::Invalid,
+            s => s.parse()?,
+        };
 
         state.halfmoves = fen[4].parse().map_err(|_| "Invalid halfmove count!")?;
         state.fullmoves = fen[5].parse().map_err(|_| "Invalid fullmove count!")?;
 
+        if lenient {
+            state.halfmoves = state.halfmoves.min(MAX_HALFMOVES);
+            state.fullmoves = state.fullmoves.max(1);
+        } else if state.halfmoves > MAX_HALFMOVES {
+            return Err("Invalid halfmove count!");
+        } else if state.fullmoves == 0 {
+            return Err("Invalid fullmove count!");
+        }
+
+        state.hash.material_key = board.calc_material_key();
         board.state = state;
+        board.settle_epsq();
+
         Ok(board)
     }
+
+    /// Only keep the en passant square, and its key in the hash, if it can actually be captured on.
+    /// This way the same position hashes the same whether it came from a FEN or from a double push.
+    pub(crate) fn settle_epsq(&mut self) {
+        if self.state.epsq != Square::Invalid {
+            if self.ep_capture_possible() {
+                self.state.hash.toggle_ep(self.state.epsq);
+            } else {
+                self.state.epsq = Square::Invalid;
+            }
+        }
+    }
+
+    /// Whether the side to move can legally capture en passant.
+    fn ep_capture_possible(&self) -> bool {
+        let epsq = self.state.epsq;
+        let ep_rank = if self.stm == Color::White { Rank::R6 } else { Rank::R3 };
+
+        if epsq.rank() != ep_rank
+            || (pawn_atk(!self.stm, epsq) & self.pc_bb(self.stm, Piece::Pawn)).is_empty()
+            || self.pc_at(epsq.forward(!self.stm)) != CPiece::make(!self.stm, Piece::Pawn)
+            || self.pc_at(epsq) != CPiece::None
+        {
+            return false;
+        }
+
+        // Only pawns can capture en passant, so don't bother generating the other pieces' moves.
+        let mut found = false;
+        let mut receiver = |m: Move| found |= m.flag() == MoveFlag::EnPassant;
+        if self.in_check() {
+            self.enumerate_pawn::<_, Noisy, true>(&mut receiver);
+        } else {
+            self.enumerate_pawn::<_, Noisy, false>(&mut receiver);
+        }
+        found
+    }
 }
 
 /// Set board according to FRC index
 impl Board {
     /// Set one side of the board according to the FRC index.
     pub fn from_frc_idx(idx: usize, dfrc: bool) -> Result<Self, &'static str> {
-        if idx > if dfrc { 960 * 960 } else { 960 } {
+        if idx >= if dfrc { 960 * 960 } else { 960 } {
             return Err("Index out of range! Expected [0..960].");
         }
 
@@ -251,6 +343,7 @@ impl Board {
         s.halfmoves = 0;
         s.fullmoves = 1;
 
+        s.hash.material_key = b.calc_material_key();
         b.state = s;
 
         Ok(b)
@@ -265,7 +358,7 @@ impl Board {
 
         let (n2, b1) = (n / 4, n % 4);
         let (n3, b2) = (n2 / 4, n2 % 4);
-        let (n4, q) = (n3 / 4, n3 % 4);
+        let (n4, q) = (n3 / 6, n3 % 6);
 
         // Add piece after the first `idx` free slots.
         let insert_into_nth_free = |pcs: &mut [Piece; 8], idx: usize, pc: Piece| {
@@ -327,13 +420,16 @@ impl Board {
     }
 
     /// Get the whole FEN in UCI format.
+    /// The en passant square is only kept if the capture is legal, so it matches what we accept on import.
     pub fn to_fen(&self) -> String {
+        let ep = if self.state.epsq != Square::Invalid { format!("{}", self.state.epsq) } else { "-".to_string() };
+
         format!(
             "{} {} {} {} {} {}",
             self.piece_placement_str(),
             self.stm,
             self.state.castling.to_str(self),
-            if self.state.epsq == Square::Invalid { "-".to_string() } else { format!("{}", self.state.epsq) },
+            ep,
             self.state.halfmoves,
             self.state.fullmoves
         )
@@ -369,6 +465,37 @@ impl fmt::Display for Board {
     }
 }
 
+/// Unicode display.
+impl Board {
+    /// Figurine glyphs, indexed by [`CPiece`].
+    const GLYPHS: [char; 13] = ['♙', '♟', '♘', '♞', '♗', '♝', '♖', '♜', '♕', '♛', '♔', '♚', ' '];
+
+    /// ANSI background colors for light and dark squares.
+    const LIGHT_SQ: &str = "\x1b[30;48;5;253m";
+    const DARK_SQ: &str = "\x1b[30;48;5;109m";
+    const RESET: &str = "\x1b[0m";
+
+    /// Display the board with unicode pieces and shaded squares, from white's perspective.
+    pub fn pretty_unicode(&self) -> String {
+        let mut s = String::new();
+        for rank in (0..8).rev() {
+            s.push_str(&format!(" {} ", rank + 1));
+
+            for file in 0..8 {
+                let shade = if (rank + file) % 2 == 0 { Self::DARK_SQ } else { Self::LIGHT_SQ };
+                let glyph = Self::GLYPHS[self.pc_map[rank * 8 + file].idx()];
+                s.push_str(&format!("{shade} {glyph} "));
+            }
+
+            s.push_str(Self::RESET);
+            s.push('\n');
+        }
+
+        s.push_str("    a  b  c  d  e  f  g  h\n");
+        s
+    }
+}
+
 /// Board implementations.
 impl Board {
     /// Get the bitboard of a given piece.
@@ -441,11 +568,35 @@ impl Board {
         self.pc_map[s.idx()] = CPiece::None;
     }
 
+    /// The number of the given piece on the board.
+    pub(crate) const fn piece_count(&self, p: CPiece) -> usize {
+        (self.pieces[p.pt().idx()].0 & self.colors[p.color().idx()].0).count_ones() as usize
+    }
+
+    /// Get a key for the material on the board.
+    /// This only depends on the number of each piece, not where they are or whose turn it is.
+    pub const fn material_key(&self) -> u64 {
+        self.state.hash.material_key
+    }
+
+    /// Calculate the material key from scratch, for when the pieces are placed directly.
+    pub fn calc_material_key(&self) -> u64 {
+        let mut h = Hash::default();
+        for p in CPiece::iter() {
+            for n in 0..self.piece_count(p) {
+                h.toggle_material(p, n);
+            }
+        }
+        h.material_key
+    }
+
     /// Find a move given a UCI move string.
+    /// Some GUIs leave out the promotion piece, so a promotion without one is taken to be a queen promotion.
     pub fn find_move(&self, s: &str) -> Option<Move> {
         let mut mv = None;
         self.enumerate_moves::<_, Allmv>(|m| {
-            if m.to_uci(&self.castlingmask) == s {
+            let uci = m.to_uci(&self.castlingmask);
+            if uci == s || (matches!(m.flag(), MoveFlag::PromoQ | MoveFlag::CPromoQ) && uci[..uci.len() - 1] == *s) {
                 mv = Some(m);
             }
         });
@@ -457,6 +608,22 @@ impl Board {
         !self.state.checkers.is_empty()
     }
 
+    /// Get the pieces giving check to the side to move.
+    pub const fn checkers(&self) -> Bitboard {
+        self.state.checkers
+    }
+
+    /// Get the pieces of the given color that are pinned to their king.
+    pub fn pinned(&self, c: Color) -> Bitboard {
+        (self.state.pin_diag[c.idx()] | self.state.pin_orth[c.idx()]) & self.c_bb(c)
+    }
+
+    /// Get the squares a non-king move must land on to resolve a single check.
+    /// This is only meaningful while in check.
+    pub const fn checkmask(&self) -> Bitboard {
+        self.state.checkmask
+    }
+
     /// Get the piece that is captured by a move.
     pub fn captured(&self, m: Move) -> CPiece {
         if m.flag() == MoveFlag::EnPassant {
@@ -472,6 +639,33 @@ impl Board {
         (self.c_bb(stm) ^ self.pc_bb(stm, Piece::King) ^ self.pc_bb(stm, Piece::Pawn)).is_empty()
     }
 
+    /// Whether the given side has nothing but its king.
+    pub fn only_king_left(&self, c: Color) -> bool {
+        (self.c_bb(c) ^ self.pc_bb(c, Piece::King)).is_empty()
+    }
+
+    /// Total value of the given side's knights, bishops, rooks and queens.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn non_pawn_material(&self, c: Color) -> i32 {
+        [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen]
+            .into_iter()
+            .map(|p| self.pc_bb(c, p).nbits() as i32 * Self::SEE_VALS[CPiece::make(c, p).idx()])
+            .sum()
+    }
+
+    /// Estimate the game phase from the remaining non-pawn material.
+    /// 24 is the opening, 0 is a pawn endgame.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn phase(&self) -> u8 {
+        let phase = self.p_bb(Piece::Knight).nbits()
+            + self.p_bb(Piece::Bishop).nbits()
+            + 2 * self.p_bb(Piece::Rook).nbits()
+            + 4 * self.p_bb(Piece::Queen).nbits();
+
+        // Promotions can take us above the starting material.
+        phase.min(24) as u8
+    }
+
     /// Generate all legal moves in the position.
     pub fn gen_moves(&self) -> MoveList {
         let mut mvs = MoveList::new();
@@ -479,6 +673,16 @@ impl Board {
         mvs
     }
 
+    /// Generate all legal moves that get out of check.
+    /// Returns no moves if we are not in check.
+    pub fn evasions(&self) -> Vec<Move> {
+        let mut mvs = Vec::new();
+        if self.in_check() {
+            self.enumerate_moves::<_, Allmv>(|m| mvs.push(m));
+        }
+        mvs
+    }
+
     /// Whether the position has any legal moves remaining.
     pub fn has_moves(&self) -> bool {
         !self.gen_moves().is_empty()
@@ -533,7 +737,10 @@ impl Board {
 
 #[cfg(test)]
 mod tests {
-    use crate::types::board::Board;
+    use crate::{
+        defs::MAX_PLY,
+        types::{bitboard::Bitboard, board::Board, color::Color, moves::MoveFlag, piece::CPiece, square::Square},
+    };
 
     #[test]
     fn test_to_fen() {
@@ -557,4 +764,293 @@ mod tests {
             assert_eq!(board.to_fen(), *fen);
         }
     }
+
+    #[test]
+    fn test_checkers_double_check() {
+        let b: Board = "4r1k1/8/8/8/8/3n4/8/4K3 w - - 0 1".parse().unwrap();
+        assert!(b.in_check());
+        assert_eq!(b.checkers(), Square::E8.bb() | Square::D3.bb());
+        assert_eq!(b.pinned(Color::White), Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn test_checkmask_single_check() {
+        let b: Board = "4r1k1/8/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        assert_eq!(b.checkers(), Square::E8.bb());
+        assert_eq!(b.checkmask(), Bitboard(0x1010_1010_1010_1000));
+    }
+
+    #[test]
+    fn test_pinned() {
+        let b: Board = "4r1k1/8/8/8/1b6/8/3NB3/4K3 w - - 0 1".parse().unwrap();
+        assert!(!b.in_check());
+        assert_eq!(b.checkers(), Bitboard::EMPTY);
+        assert_eq!(b.pinned(Color::White), Square::D2.bb() | Square::E2.bb());
+        assert_eq!(b.pinned(Color::Black), Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn test_move_counter_validation() {
+        const ZERO_FULLMOVE: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0";
+        const HUGE_HALFMOVE: &str = "8/8/8/r4k2/2K5/8/8/8 w - - 4000 80";
+
+        assert!(ZERO_FULLMOVE.parse::<Board>().is_err());
+        assert!(HUGE_HALFMOVE.parse::<Board>().is_err());
+        assert!("8/8/8/r4k2/2K5/8/8/8 w - - 150 80".parse::<Board>().is_ok());
+        assert!("8/8/8/r4k2/2K5/8/8/8 w - - 151 80".parse::<Board>().is_err());
+
+        let b = Board::from_fen_lenient(ZERO_FULLMOVE).unwrap();
+        assert_eq!(b.state.fullmoves, 1);
+        assert_eq!(b.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        let b = Board::from_fen_lenient(HUGE_HALFMOVE).unwrap();
+        assert_eq!(b.state.halfmoves, 150);
+        assert_eq!(b.state.fullmoves, 80);
+    }
+
+    #[test]
+    fn test_malformed_fen() {
+        const FENS: &[&str] = &[
+            "",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq z9 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - a b",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8 w KQkq - 0 1",
+        ];
+
+        for fen in FENS {
+            assert!(fen.parse::<Board>().is_err(), "{fen}");
+            assert!(Board::from_fen_lenient(fen).is_err(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_pretty_unicode() {
+        let b = Board::default();
+        let pretty = b.pretty_unicode();
+        let lines = pretty.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 9);
+
+        // Strip the shading so that only the rank label and pieces remain.
+        let glyphs = |l: &str| l.split('m').skip(1).filter_map(|cell| cell.chars().nth(1)).collect::<String>();
+
+        assert!(lines[0].starts_with(" 8 "));
+        assert_eq!(glyphs(lines[0]), "♜♞♝♛♚♝♞♜");
+        assert_eq!(glyphs(lines[1]), "♟♟♟♟♟♟♟♟");
+        assert_eq!(glyphs(lines[4]), "        ");
+        assert_eq!(glyphs(lines[6]), "♙♙♙♙♙♙♙♙");
+        assert_eq!(glyphs(lines[7]), "♖♘♗♕♔♗♘♖");
+        assert!(lines[7].starts_with(" 1 "));
+
+        // a1 is a dark square, h1 is a light square.
+        assert!(lines[7].starts_with(&format!(" 1 {} ♖", Board::DARK_SQ)));
+        assert!(lines[7].contains(&format!("{} ♖ {}", Board::LIGHT_SQ, Board::RESET)));
+    }
+
+    #[test]
+    fn test_find_move_promotions() {
+        let b: Board = "3r1k2/4P3/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+
+        assert_eq!(b.find_move("e7e8").map(|m| m.flag()), Some(MoveFlag::PromoQ));
+        assert_eq!(b.find_move("e7e8n").map(|m| m.flag()), Some(MoveFlag::PromoN));
+        assert_eq!(b.find_move("e7d8").map(|m| m.flag()), Some(MoveFlag::CPromoQ));
+        assert_eq!(b.find_move("e7d8r").map(|m| m.flag()), Some(MoveFlag::CPromoR));
+
+        // Non-promotions are unaffected.
+        assert!(b.find_move("e1e").is_none());
+        assert!(b.find_move("e1e2").is_some());
+    }
+
+    #[test]
+    fn test_strict_material_validation() {
+        const NINE_PAWNS: &str = "4k3/8/8/8/4P3/8/PPPPPPPP/4K3 w - - 0 1";
+        const BACK_RANK_PAWN: &str = "4k3/8/8/8/8/8/8/P3K3 w - - 0 1";
+
+        assert!(NINE_PAWNS.parse::<Board>().is_ok());
+        assert!(Board::from_fen_strict(NINE_PAWNS).is_err());
+
+        assert!(BACK_RANK_PAWN.parse::<Board>().is_ok());
+        assert!(Board::from_fen_strict(BACK_RANK_PAWN).is_err());
+
+        assert!(Board::from_fen_strict("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_ok());
+    }
+
+    #[test]
+    fn test_material_key() {
+        let key = |fen: &str| fen.parse::<Board>().unwrap().material_key();
+
+        // Only the material matters, not where it is or whose turn it is.
+        assert_eq!(key("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), key("4k3/8/8/3R4/8/8/8/4K3 b - - 0 1"));
+        assert_ne!(key("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), key("4k3/8/8/8/8/8/8/R3K2R w - - 0 1"));
+
+        // Recoloring changes the key, unless the material is symmetric.
+        assert_ne!(key("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), key("r3k3/8/8/8/8/8/8/4K3 b - - 0 1"));
+        assert_eq!(
+            key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            key("RNBQKBNR/PPPPPPPP/8/8/8/8/pppppppp/rnbqkbnr b - - 0 1")
+        );
+
+        // Make and undo should keep the key in sync with the board.
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkb1r/pp1p1pPp/8/2p1pP2/1P1P4/3P3P/P1P1P3/RNBQKBNR w KQkq e6 0 1",
+        ] {
+            let mut b: Board = fen.parse().unwrap();
+            let before = b.material_key();
+
+            for m in b.gen_moves() {
+                b.make_move(m);
+                assert_eq!(b.material_key(), key(&b.to_fen()), "{fen}: {m:?}");
+                assert_eq!(b.material_key() == before, !m.flag().is_cap() && !m.flag().is_promo(), "{fen}: {m:?}");
+                b.undo_move();
+                assert_eq!(b.material_key(), before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_frc_idx() {
+        let fen = |idx, dfrc| Board::from_frc_idx(idx, dfrc).unwrap().to_fen();
+
+        assert_eq!(fen(518, false), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(fen(0, false), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KFkf - 0 1");
+        assert_eq!(fen(959, false), "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w CQcq - 0 1");
+        assert_eq!(fen(518 * 960, true), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KFkq - 0 1");
+
+        assert!(Board::from_frc_idx(960, false).is_err());
+        assert!(Board::from_frc_idx(960 * 960, true).is_err());
+    }
+
+    #[test]
+    fn test_phase() {
+        let phase = |fen: &str| fen.parse::<Board>().unwrap().phase();
+
+        assert_eq!(phase("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 24);
+        assert_eq!(phase("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), 0);
+        assert_eq!(phase("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1"), 0);
+        assert_eq!(phase("r3k3/8/8/8/8/8/8/4K1N1 w - - 0 1"), 3);
+        assert_eq!(phase("qqqqk3/8/8/8/8/8/8/QQQQK3 w - - 0 1"), 24);
+    }
+
+    #[test]
+    fn test_material_helpers() {
+        // KPvK.
+        let b: Board = "8/8/4k3/8/8/4P3/4K3/8 w - - 0 1".parse().unwrap();
+        assert!(b.only_king_pawns_left());
+        assert!(!b.only_king_left(Color::White));
+        assert!(b.only_king_left(Color::Black));
+        assert_eq!(b.non_pawn_material(Color::White), 0);
+        assert_eq!(b.non_pawn_material(Color::Black), 0);
+
+        // KRvK.
+        let b: Board = "8/8/4k3/8/8/8/4K3/R7 b - - 0 1".parse().unwrap();
+        assert!(b.only_king_pawns_left());
+        assert!(b.only_king_left(Color::Black));
+        assert!(!b.only_king_left(Color::White));
+        assert_eq!(b.non_pawn_material(Color::White), Board::SEE_VALS[CPiece::WRook.idx()]);
+        assert_eq!(b.non_pawn_material(Color::Black), 0);
+
+        let b: Board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+        assert_eq!(b.non_pawn_material(Color::White), b.non_pawn_material(Color::Black));
+        assert!(!b.only_king_pawns_left());
+    }
+
+    #[test]
+    fn test_reserve_history() {
+        let mut b: Board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+        b.make_move(b.find_move("b1c3").unwrap());
+
+        // Clones only keep the history they are using.
+        let mut b = b.clone();
+        let fen = b.to_fen();
+        b.reserve_history();
+        let capacity = b.history.capacity();
+
+        // Shuffle the knights back and forth for a full search worth of plies.
+        for _ in 0..MAX_PLY / 4 {
+            for uci in ["g8f6", "g1f3", "f6g8", "f3g1"] {
+                b.make_move(b.find_move(uci).unwrap());
+            }
+            assert!(b.is_repetition(4));
+        }
+
+        assert_eq!(b.history.capacity(), capacity);
+
+        while b.history.len() > 1 {
+            b.undo_move();
+        }
+        assert_eq!(b.to_fen(), fen);
+    }
+
+    #[test]
+    fn test_ep_square_import() {
+        // No black pawn can capture on e3.
+        let b: Board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".parse().unwrap();
+        let x: Board = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".parse().unwrap();
+        assert_eq!(b.state.epsq, Square::Invalid);
+        assert_eq!(b.state.hash, x.state.hash);
+
+        // The d4 pawn can capture on e3.
+        let b: Board = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3".parse().unwrap();
+        let x: Board = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3".parse().unwrap();
+        assert_eq!(b.state.epsq, Square::E3);
+        assert_ne!(b.state.hash, x.state.hash);
+
+        // The f4 pawn is pinned against the king along the rank.
+        let b: Board = "8/8/8/8/R3Ppk1/8/8/4K3 b - e3 0 1".parse().unwrap();
+        assert_eq!(b.state.epsq, Square::Invalid);
+    }
+
+    #[test]
+    fn test_ep_square_export() {
+        let after = |fen: &str, uci: &str| {
+            let mut b: Board = fen.parse().unwrap();
+            b.make_move(b.find_move(uci).unwrap());
+            b.to_fen()
+        };
+
+        // No black pawn can capture on e3.
+        let fen = after("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4");
+        assert_eq!(fen, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+
+        // The d4 pawn can capture on e3.
+        let fen = after("rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 3", "e2e4");
+        assert_eq!(fen, "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3");
+
+        // The f4 pawn is pinned against the king along the rank.
+        let fen = after("8/8/8/8/R4pk1/8/4P3/4K3 w - - 0 1", "e2e4");
+        assert_eq!(fen, "8/8/8/8/R3Ppk1/8/8/4K3 b - - 0 1");
+
+        // Exported FENs import to the same en passant square.
+        for fen in ["rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3", "8/8/8/8/R3Ppk1/8/8/4K3 b - e3 0 1"] {
+            let b: Board = fen.parse().unwrap();
+            assert_eq!(b.to_fen().parse::<Board>().unwrap().state.epsq, b.state.epsq);
+        }
+    }
+
+    #[test]
+    fn test_evasions_double_check() {
+        let b: Board = "4r1k1/8/8/8/8/3n4/8/4K3 w - - 0 1".parse().unwrap();
+        let evasions = b.evasions();
+        assert!(!evasions.is_empty());
+        assert!(evasions.iter().all(|m| m.src() == Square::E1));
+    }
+
+    #[test]
+    fn test_evasions_single_check() {
+        let b: Board = "R3r1k1/8/8/8/8/8/3B4/4K3 w - - 0 1".parse().unwrap();
+        let evasions = b.evasions();
+
+        for m in ["a8e8", "d2e3", "e1f1"] {
+            assert!(evasions.contains(&b.find_move(m).unwrap()));
+        }
+
+        assert!(evasions.iter().all(|m| m.src() == Square::E1 || b.checkmask().has(m.dst())));
+        assert_eq!(evasions.len(), b.gen_moves().len());
+
+        assert!(Board::default().evasions().is_empty());
+    }
 }
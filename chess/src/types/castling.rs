@@ -163,10 +163,19 @@ impl CastlingRights {
             let rook = CPiece::make(c, Piece::Rook);
             let ksq = b.ksq(c);
 
+            // The king must be on the back rank to castle.
+            if ksq.rank() != Rank::R1.relative(c) {
+                return Err("Invalid Castling Rights!");
+            }
+
+            // Use the outermost rook, which must be on the correct side of the king.
             let (rsq, mask) = match t {
                 'K' => {
                     let mut sq = Square::H1.relative(c);
                     while b.pc_at(sq) != rook {
+                        if sq == ksq {
+                            return Err("Invalid Castling Rights!");
+                        }
                         sq = sq.prev();
                     }
                     (sq, Self::get_mask(c, false))
@@ -174,6 +183,9 @@ impl CastlingRights {
                 'Q' => {
                     let mut sq = Square::A1.relative(c);
                     while b.pc_at(sq) != rook {
+                        if sq == ksq {
+                            return Err("Invalid Castling Rights!");
+                        }
                         sq = sq.next();
                     }
                     (sq, Self::get_mask(c, true))
@@ -182,6 +194,9 @@ impl CastlingRights {
                 'A'..='H' => {
                     c_mask.frc = true;
                     let sq = Square::make(Rank::R1.relative(c), File::from_raw(t as u8 - b'A'));
+                    if b.pc_at(sq) != rook {
+                        return Err("Invalid Castling Rights!");
+                    }
                     (sq, Self::get_mask(c, ksq > sq))
                 }
 
@@ -236,3 +251,54 @@ impl CastlingRights {
         s
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_castling_round_trip() {
+        let castling = |fen: &str| fen.split_whitespace().nth(2).unwrap().to_owned();
+
+        // Walk a few moves into a spread of DFRC games, so that rooks and kings have moved around.
+        for idx in (0..960 * 960).step_by(997) {
+            let mut b = Board::from_frc_idx(idx, true).unwrap();
+
+            for ply in 0..40 {
+                let fen = b.to_fen();
+                let parsed: Board = fen.parse().unwrap();
+
+                assert_eq!(castling(&parsed.to_fen()), castling(&fen), "{fen}");
+                assert_eq!(parsed.state.castling, b.state.castling, "{fen}");
+                for i in 0..4 {
+                    if b.state.castling.0 & (1 << i) != 0 {
+                        assert_eq!(parsed.castlingmask.rooks[i], b.castlingmask.rooks[i], "{fen}");
+                    }
+                }
+
+                let moves = b.gen_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                b.make_move(moves[(idx + ply * 13) % moves.len()]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_castling_parse_strict() {
+        // No rook on the given side of the king.
+        assert!("4k3/8/8/8/8/8/8/R3K3 w K - 0 1".parse::<Board>().is_err());
+        assert!("4k3/8/8/8/8/8/8/4K2R w Q - 0 1".parse::<Board>().is_err());
+
+        // No rook on the given file.
+        assert!("4k3/8/8/8/8/8/8/R3K2R w G - 0 1".parse::<Board>().is_err());
+
+        // King not on the back rank.
+        assert!("4k3/8/8/8/8/8/4K3/R6R w KQ - 0 1".parse::<Board>().is_err());
+
+        // Shredder style rights for the standard rooks are written back as KQkq.
+        let b: Board = "r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1".parse().unwrap();
+        assert!(b.to_fen().contains(" KQkq "));
+    }
+}
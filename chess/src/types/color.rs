@@ -16,6 +16,7 @@ impl_from_type! {
 
 impl Color {
     /// Iterate over both colors.
+    /// The order is guaranteed to be white, then black.
     pub fn iter() -> impl Iterator<Item = Self> {
         [Self::White, Self::Black].into_iter()
     }
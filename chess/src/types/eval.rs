@@ -18,6 +18,11 @@ pub struct Eval(pub i32);
 
 impl_all_math_ops! (Eval: i32);
 
+/// The internal eval that corresponds to a one pawn advantage.
+/// TODO: Feed it more games
+/// <https://github.com/official-stockfish/WDL_model>
+const NORMALIZE_PAWN_VALUE: i32 = 168;
+
 impl Eval {
     pub const DRAW: Self = Self(0);
     pub const TB_MATE: Self = Self(31000);
@@ -42,6 +47,17 @@ impl Eval {
         Self(self.0.min(other.0))
     }
 
+    /// Adds to this eval, saturating at [`Eval::INFINITY`] in either direction.
+    /// Use this when building search windows, which may already be at the limits.
+    pub fn saturating_add(self, v: i32) -> Self {
+        Self(self.0.saturating_add(v).clamp(-Self::INFINITY.0, Self::INFINITY.0))
+    }
+
+    /// Subtracts from this eval, saturating at [`Eval::INFINITY`] in either direction.
+    pub fn saturating_sub(self, v: i32) -> Self {
+        Self(self.0.saturating_sub(v).clamp(-Self::INFINITY.0, Self::INFINITY.0))
+    }
+
     /// Gets the midpoint between two evaluations.
     pub const fn midpoint(a: Self, b: Self) -> Self {
         Self(i32::midpoint(a.0, b.0))
@@ -130,15 +146,36 @@ impl Eval {
         }
     }
 
-    /// Normalizes the evaluation.
-    /// TODO: Feed it more games
-    /// <https://github.com/official-stockfish/WDL_model>
-    pub const fn to_centipawns(self) -> i32 {
-        const NORMALIZE_PAWN_VALUE: i32 = 168;
-
+    /// Normalizes the evaluation to centipawns.
+    /// Terminal scores are passed through unchanged.
+    pub const fn to_cp(self) -> i32 {
         if !self.is_terminal() { (self.0 * 100) / NORMALIZE_PAWN_VALUE } else { self.0 }
     }
 
+    /// Gets the eval from a normalized centipawn score.
+    /// Terminal scores are passed through unchanged.
+    pub const fn from_cp(cp: i32) -> Self {
+        let v = Self(cp);
+        if v.is_terminal() {
+            return v;
+        }
+
+        // Round away from zero so that converting back gives the same centipawns.
+        let raw = cp.signum() * ((cp.abs() * NORMALIZE_PAWN_VALUE + 99) / 100);
+        Self(if raw.abs() < Self::LONGEST_TB_MATE.0 { raw } else { (Self::LONGEST_TB_MATE.0 - 1) * raw.signum() })
+    }
+
+    /// The number of moves until mate, negative if we are getting mated.
+    /// Returns None if this is not a mate score.
+    pub const fn mate_distance(self) -> Option<i32> {
+        if !self.is_terminal() {
+            return None;
+        }
+
+        let moves_to_mate = (Self::MATE.0 - self.abs().0 + 1) / 2;
+        Some(if self.0 > Self::DRAW.0 { moves_to_mate } else { -moves_to_mate })
+    }
+
     /// Clamps eval to the valid (non-terminal) range.
     pub fn clamp_to_nonterminal(self) -> Self {
         Self(self.0.clamp(-Self::LONGEST_TB_MATE.0 + 1, Self::LONGEST_TB_MATE.0 - 1))
@@ -148,12 +185,9 @@ impl Eval {
 /// Display the eval according to UCI format.
 impl fmt::Display for Eval {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if !self.is_terminal() {
-            write!(f, "cp {}", self.to_centipawns())
-        } else {
-            let moves_to_mate = (Self::MATE.0 - self.abs().0 + 1) / 2;
-            let sign = if *self > Self::DRAW { "" } else { "-" };
-            write!(f, "mate {sign}{moves_to_mate}")
+        match self.mate_distance() {
+            Some(n) => write!(f, "mate {n}"),
+            None => write!(f, "cp {}", self.to_cp()),
         }
     }
 }
@@ -165,3 +199,59 @@ impl std::ops::Neg for Eval {
         Self(-self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cp_round_trip() {
+        for cp in -2000..=2000 {
+            assert_eq!(Eval::from_cp(cp).to_cp(), cp);
+        }
+
+        for raw in -5000..=5000 {
+            let v = Eval(raw);
+            assert!((Eval::from_cp(v.to_cp()) - v).abs() <= Eval(NORMALIZE_PAWN_VALUE / 100 + 1));
+        }
+    }
+
+    #[test]
+    fn test_cp_mate_passthrough() {
+        for v in [Eval::search_mate_in(5), Eval::search_mated_in(8), Eval::tb_mate_in(20), Eval::tb_mated_in(3)] {
+            assert_eq!(v.to_cp(), v.0);
+            assert_eq!(Eval::from_cp(v.to_cp()), v);
+        }
+
+        assert!(!Eval::from_cp(Eval::LONGEST_TB_MATE.0 - 1).is_terminal());
+    }
+
+    #[test]
+    fn test_saturating_ops() {
+        assert_eq!(Eval::INFINITY.saturating_add(1), Eval::INFINITY);
+        assert_eq!(Eval::INFINITY.saturating_add(i32::MAX), Eval::INFINITY);
+        assert_eq!((-Eval::INFINITY).saturating_sub(1), -Eval::INFINITY);
+        assert_eq!((-Eval::INFINITY).saturating_sub(i32::MAX), -Eval::INFINITY);
+        assert_eq!((-Eval::INFINITY).saturating_add(i32::MIN), -Eval::INFINITY);
+
+        assert_eq!(Eval::INFINITY.saturating_sub(1), Eval(Eval::INFINITY.0 - 1));
+        assert_eq!(Eval(100).saturating_add(50), Eval(150));
+        assert_eq!(Eval(100).saturating_sub(50), Eval(50));
+    }
+
+    #[test]
+    fn test_display_mate() {
+        assert_eq!(Eval::search_mate_in(5).to_string(), "mate 3");
+        assert_eq!(Eval::search_mated_in(4).to_string(), "mate -2");
+        assert_eq!(format!("{} lowerbound", Eval::search_mate_in(1)), "mate 1 lowerbound");
+        assert_eq!(Eval::from_cp(-35).to_string(), "cp -35");
+    }
+
+    #[test]
+    fn test_mate_distance() {
+        assert_eq!(Eval::search_mate_in(1).mate_distance(), Some(1));
+        assert_eq!(Eval::search_mated_in(6).mate_distance(), Some(-3));
+        assert_eq!(Eval::from_cp(250).mate_distance(), None);
+        assert_eq!(Eval::DRAW.mate_distance(), None);
+    }
+}
@@ -43,6 +43,7 @@ impl Piece {
     const UCI_CHAR: &str = "pnbrqk ";
 
     /// Iterate over all [`Piece`]s.
+    /// The order is guaranteed to be pawn, knight, bishop, rook, queen, king.
     pub fn iter() -> impl Iterator<Item = Self> {
         (0..6).map(Self::from_raw)
     }
@@ -51,6 +52,12 @@ impl Piece {
     pub fn to_char(self) -> char {
         Self::UCI_CHAR.chars().nth(self as usize).unwrap_or('?')
     }
+
+    /// Get the [`Piece`] from a UCI character, ignoring case.
+    /// Returns an error if the provided `char` does not match any piece.
+    pub fn from_char(c: char) -> Result<Self, &'static str> {
+        Self::iter().find(|p| p.to_char() == c.to_ascii_lowercase()).ok_or("Invalid Piece!")
+    }
 }
 
 impl fmt::Display for Piece {
@@ -78,6 +85,7 @@ impl CPiece {
     }
 
     /// Iterate over all [`CPiece`]s.
+    /// The order is guaranteed to be by [`Piece`] order, with white before black for each piece.
     pub fn iter() -> impl Iterator<Item = Self> {
         (0..12).map(Self::from_raw)
     }
@@ -86,6 +94,12 @@ impl CPiece {
     pub fn to_char(self) -> char {
         Self::UCI_CHAR.chars().nth(self as usize).unwrap_or('?')
     }
+
+    /// Get the [`CPiece`] from a FEN character: uppercase for white, lowercase for black.
+    /// Returns an error if the provided `char` does not match any piece.
+    pub fn from_fen_char(c: char) -> Result<Self, &'static str> {
+        Self::try_from(c)
+    }
 }
 
 impl fmt::Display for CPiece {
@@ -100,6 +114,38 @@ impl TryFrom<char> for CPiece {
     /// Constructs a piece from a given character according to UCI specification.
     /// Returns an error (`&' static str`) if the provided `char` does not match any piece.
     fn try_from(value: char) -> Result<Self, Self::Error> {
-        Ok(Self::from_raw(Self::UCI_CHAR.chars().position(|x| x == value).ok_or("Invalid CPiece!")? as u8))
+        Self::iter().find(|p| p.to_char() == value).ok_or("Invalid CPiece!")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piece_char_round_trip() {
+        for p in Piece::iter() {
+            assert_eq!(Piece::from_char(p.to_char()), Ok(p));
+            assert_eq!(Piece::from_char(p.to_char().to_ascii_uppercase()), Ok(p));
+        }
+
+        for p in CPiece::iter() {
+            assert_eq!(CPiece::from_fen_char(p.to_char()), Ok(p));
+            assert_eq!(CPiece::try_from(p.to_char()), Ok(p));
+            assert_eq!(Piece::from_char(p.to_char()), Ok(p.pt()));
+            assert_eq!(p.to_char().is_ascii_uppercase(), p.color() == Color::White);
+        }
+
+        for c in [' ', 'x', '1', '?'] {
+            assert!(Piece::from_char(c).is_err());
+            assert!(CPiece::from_fen_char(c).is_err());
+        }
+    }
+
+    #[test]
+    fn test_iter_order() {
+        assert_eq!(Piece::iter().map(Piece::to_char).collect::<String>(), "pnbrqk");
+        assert_eq!(CPiece::iter().map(CPiece::to_char).collect::<String>(), "PpNnBbRrQqKk");
+        assert_eq!(Color::iter().collect::<Vec<_>>(), [Color::White, Color::Black]);
     }
 }
@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use crate::{
     impl_from_type,
+    tables::sliding_piece::between,
     types::{
         bitboard::Bitboard,
         color::Color,
@@ -94,6 +95,21 @@ impl Square {
     pub fn iter() -> impl Iterator<Item = Self> {
         (0..64).map(Self::from_raw)
     }
+
+    /// The squares strictly between two squares on the same rank, file or diagonal.
+    /// This is empty if the squares are not aligned, or are next to each other.
+    pub const fn ray_between(a: Self, b: Self) -> Bitboard {
+        between(a, b)
+    }
+
+    /// Whether three different squares all lie on the same rank, file or diagonal.
+    pub const fn aligned(a: Self, b: Self, c: Self) -> bool {
+        // If they are aligned, one of them must be between the other two.
+        a as u8 != b as u8
+            && b as u8 != c as u8
+            && a as u8 != c as u8
+            && (between(a, b).has(c) || between(a, c).has(b) || between(b, c).has(a))
+    }
 }
 
 /// Convert a string to a Square
@@ -129,3 +145,35 @@ impl fmt::Display for Square {
         write!(f, "{}{}", (b'a' + file) as char, (b'1' + rank) as char)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ray_between() {
+        assert_eq!(Square::ray_between(Square::A1, Square::D1), Square::B1.bb() | Square::C1.bb());
+        assert_eq!(Square::ray_between(Square::D1, Square::A1), Square::B1.bb() | Square::C1.bb());
+        assert_eq!(Square::ray_between(Square::B2, Square::E5), Square::C3.bb() | Square::D4.bb());
+        assert_eq!(Square::ray_between(Square::E2, Square::E4), Square::E3.bb());
+
+        // Neighbours and unaligned squares have nothing between them.
+        assert!(Square::ray_between(Square::E4, Square::E5).is_empty());
+        assert!(Square::ray_between(Square::A1, Square::B3).is_empty());
+    }
+
+    #[test]
+    fn test_aligned() {
+        // Rank, file and both diagonals, in any order.
+        assert!(Square::aligned(Square::A1, Square::B1, Square::H1));
+        assert!(Square::aligned(Square::E8, Square::E1, Square::E4));
+        assert!(Square::aligned(Square::C3, Square::A1, Square::H8));
+        assert!(Square::aligned(Square::A8, Square::H1, Square::D5));
+        assert!(Square::aligned(Square::F3, Square::G2, Square::H1));
+
+        assert!(!Square::aligned(Square::A1, Square::B1, Square::B2));
+        assert!(!Square::aligned(Square::A1, Square::B3, Square::C5));
+        assert!(!Square::aligned(Square::A1, Square::C2, Square::E4));
+        assert!(!Square::aligned(Square::E4, Square::E4, Square::E5));
+    }
+}
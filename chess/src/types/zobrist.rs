@@ -19,6 +19,7 @@ pub struct Hash {
     pub key: u64,
     pub pawn_key: u64,
     pub non_pawn_key: [u64; Color::NUM],
+    pub material_key: u64,
 }
 
 /// Print out the Hash.
@@ -46,6 +47,11 @@ impl Hash {
         }
     }
 
+    /// Toggle the nth piece of a type (counting from zero) on or off in the material key.
+    pub const fn toggle_material(&mut self, p: CPiece, n: usize) {
+        self.material_key ^= MATERIAL_KEYS[p.idx()][n];
+    }
+
     /// Toggle castling rights on or off.
     pub const fn toggle_castling(&mut self, cr: CastlingRights) {
         self.key ^= CASTLING_KEYS[cr.idx()];
@@ -81,6 +87,26 @@ pub(crate) static PIECE_KEYS: [[u64; Square::NUM]; CPiece::NUM] = {
     piece_sq
 };
 
+/// The bits to toggle on or off when we have a given number of a piece.
+/// The key for the nth piece of a type is toggled when it is added or removed.
+static MATERIAL_KEYS: [[u64; Square::NUM]; CPiece::NUM] = {
+    let mut material = [[0; Square::NUM]; CPiece::NUM];
+    let mut state = 0x5F7C_3A19_B2E4_8D61;
+
+    let mut p = 0;
+    while p < CPiece::NUM {
+        let mut n = 0;
+        while n < Square::NUM {
+            material[p][n] = state;
+            state = next_rng(state);
+            n += 1;
+        }
+        p += 1;
+    }
+
+    material
+};
+
 /// The bits to toggle on or off when we have some castling rights.
 static CASTLING_KEYS: [u64; CastlingRights::NUM] = {
     let mut castling = [0; CastlingRights::NUM];
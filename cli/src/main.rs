@@ -1,11 +1,14 @@
 use std::path::PathBuf;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use cli::uci::UCIReader;
-use engine::bench::run_bench;
 #[cfg(feature = "tune")]
 use engine::tunables::params::tunables;
+use engine::{
+    bench::{BENCH_DEPTH, BENCH_HASH, BenchOpts, EVAL_BENCH_ITERS, run_bench, run_eval_bench},
+    search::pruning::lmr_table,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "Venus")]
@@ -18,13 +21,44 @@ struct Args {
 #[derive(Subcommand, Debug)]
 enum Command {
     /// Runs a benchmark against a number of set test positions
-    Bench { epd: Option<PathBuf> },
+    Bench {
+        /// File of FENs to use instead of the default positions
+        epd: Option<PathBuf>,
+
+        /// Depth to search each position to
+        #[arg(long, default_value_t = BENCH_DEPTH)]
+        depth: i16,
+
+        /// Hash size in MB
+        #[arg(long, default_value_t = BENCH_HASH)]
+        hash: usize,
+
+        /// Number of search threads
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
+
+        /// Measure raw NNUE evaluation throughput instead of searching
+        #[arg(long, conflicts_with_all = ["epd", "depth", "hash", "threads"])]
+        eval: bool,
+    },
+
+    /// Prints out internal tables for inspection
+    Dump {
+        #[arg(value_enum)]
+        table: DumpTable,
+    },
 
     /// Outputs a list of the SPSA parameters for openbench
     #[cfg(feature = "tune")]
     Spsa,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DumpTable {
+    /// Late move reduction table, indexed by [depth][moves tried]
+    Lmr,
+}
+
 fn main() -> Result<()> {
     #[cfg(not(feature = "embed"))]
     println!("WARNING: engine does not have eval network. If you want to build the engine, make sure to build with the 'embed' feature.");
@@ -32,7 +66,19 @@ fn main() -> Result<()> {
     let args = Args::parse();
 
     match args.command {
-        Some(Command::Bench { epd }) => run_bench(epd),
+        Some(Command::Bench { eval: true, .. }) => {
+            run_eval_bench(EVAL_BENCH_ITERS);
+            Ok(())
+        }
+
+        Some(Command::Bench { epd, depth, hash, threads, .. }) => run_bench(BenchOpts { depth, hash_mb: hash, threads, epd_path: epd }),
+
+        Some(Command::Dump { table: DumpTable::Lmr }) => {
+            for row in lmr_table() {
+                println!("{}", row.map(|r| format!("{r:>2}")).join(" "));
+            }
+            Ok(())
+        }
 
         #[cfg(feature = "tune")]
         Some(Command::Spsa) => {
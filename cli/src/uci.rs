@@ -1,5 +1,6 @@
 use std::{
     io::{self, BufRead},
+    path::PathBuf,
     str::SplitWhitespace,
 };
 
@@ -8,8 +9,8 @@ use anyhow::{Result, anyhow};
 use engine::tunables::params::tunables;
 use engine::{
     VERSION,
-    bench::run_bench,
-    interface::{EngineCommand, EngineInterface},
+    bench::{BenchOpts, EVAL_BENCH_ITERS, run_bench, run_eval_bench},
+    interface::{EngineCommand, EngineInterface, uci_options},
     position::Position,
     time_management::timecontrol::TimeControl,
 };
@@ -21,19 +22,6 @@ fn authors() -> String {
     env!("CARGO_PKG_AUTHORS").replace(':', ", ")
 }
 
-pub const OPTS: &str = "
-option name UCI_Chess960 type check default false
-option name Threads type spin default 1 min 1 max 128
-option name Hash type spin default 16 min 1 max 65536
-option name Clear Hash type button";
-
-#[cfg(feature = "syzygy")]
-pub const SYZYGY_OPTS: &str = "
-option name SyzygyPath type string default";
-
-#[cfg(not(feature = "syzygy"))]
-const SYZYGY_OPTS: &str = "";
-
 #[derive(Default)]
 pub struct UCIReader {
     interface: EngineInterface,
@@ -47,17 +35,23 @@ impl UCIReader {
 
         let stdin = io::stdin().lock();
         for line in stdin.lines() {
-            let line = line?;
-            match self.parse_command(&line) {
-                Ok(true) => return Ok(()),
-                Ok(false) => {}
-                Err(e) => eprintln!("{e}"),
+            if self.handle_line(&line?) {
+                return Ok(());
             }
         }
 
         Ok(())
     }
 
+    /// Handle a single line of input. Returns true if the engine should quit.
+    /// Malformed input is reported to the GUI, and never stops the reader.
+    fn handle_line(&self, line: &str) -> bool {
+        self.parse_command(line).unwrap_or_else(|e| {
+            println!("info string error: {e}");
+            false
+        })
+    }
+
     /// Parse a UCI command. Returns true if the engine should quit.
     #[rustfmt::skip]
     fn parse_command(&self, s: &str) -> Result<bool> {
@@ -67,7 +61,7 @@ impl UCIReader {
             Some(cmd) => match cmd {
                 "quit"           => { self.interface.handle_command(EngineCommand::Stop); return Ok(true); }
                 "isready"        => println!("readyok"),
-                "bench"          => run_bench(None)?,
+                "bench"          => self.cmd_bench(&mut tokens)?,
                 "uci"            => self.cmd_uci(),
                 "ucinewgame"     => self.interface.handle_command(EngineCommand::NewGame),
                 "stop"           => self.interface.handle_command(EngineCommand::Stop),
@@ -80,6 +74,8 @@ impl UCIReader {
                 "setoption"      => self.cmd_setoption(&mut tokens)?,
                 "move" | "m"     => self.cmd_move(&mut tokens)?,
                 "undo" | "u"     => self.interface.handle_command(EngineCommand::Undo),
+                "debug"          => self.cmd_debug(&mut tokens)?,
+                "tt"             => self.cmd_tt(&mut tokens)?,
                 _ => return Err(anyhow!("Unknown command!"))
             },
             None => return Err(anyhow!("Empty command!")),
@@ -106,14 +102,43 @@ impl UCIReader {
     pub fn cmd_uci(&self) {
         println!("id name {NAME}-{VERSION}");
         println!("id author {}", authors());
-        println!("{OPTS}{SYZYGY_OPTS}");
+        println!("{}", uci_options());
 
         #[cfg(feature = "tune")]
         println!("{}", tunables::spsa_output_opts());
 
+        println!("info string {}", nnue::embed::net_info());
         println!("uciok");
     }
 
+    /// bench command.
+    /// Usage: bench [eval | <depth> <hash> <threads> [epd file]]
+    pub fn cmd_bench(&self, tokens: &mut SplitWhitespace) -> Result<()> {
+        let mut tokens = tokens.peekable();
+        if tokens.next_if_eq(&"eval").is_some() {
+            run_eval_bench(EVAL_BENCH_ITERS);
+            return Ok(());
+        }
+
+        let mut opts = BenchOpts::default();
+
+        if let Some(t) = tokens.next() {
+            opts.depth = t.parse().map_err(|_| anyhow!("Invalid depth value!"))?;
+        }
+
+        if let Some(t) = tokens.next() {
+            opts.hash_mb = t.parse().map_err(|_| anyhow!("Invalid hash value!"))?;
+        }
+
+        if let Some(t) = tokens.next() {
+            opts.threads = t.parse().map_err(|_| anyhow!("Invalid threads value!"))?;
+        }
+
+        opts.epd_path = tokens.next().map(PathBuf::from);
+
+        run_bench(opts)
+    }
+
     /// perft command.
     pub fn cmd_perft(&self, tokens: &mut SplitWhitespace) -> Result<()> {
         let depth = parse_depth(tokens)?;
@@ -143,21 +168,48 @@ impl UCIReader {
     }
 
     /// setoption command.
+    /// Usage: setoption name <name> [value <value>]
+    /// Both the name and value may contain spaces. Buttons have no value.
     pub fn cmd_setoption(&self, tokens: &mut SplitWhitespace) -> Result<()> {
         if tokens.next() != Some("name") {
             return Err(anyhow!("Invalid option command!"));
         }
-        let name = tokens.next().ok_or_else(|| anyhow!("No option name!"))?.to_owned();
 
-        if tokens.next() != Some("value") {
-            return Err(anyhow!("Invalid option command!"));
+        let name = tokens.by_ref().take_while(|&t| t != "value").collect::<Vec<_>>().join(" ");
+        if name.is_empty() {
+            return Err(anyhow!("No option name!"));
         }
-        let value = tokens.next().ok_or_else(|| anyhow!("No option value!"))?.to_owned();
+
+        let value = tokens.collect::<Vec<_>>().join(" ");
 
         self.interface.handle_command(EngineCommand::SetOpt(name, value));
         Ok(())
     }
 
+    /// debug command.
+    /// Usage: debug [on | off]
+    pub fn cmd_debug(&self, tokens: &mut SplitWhitespace) -> Result<()> {
+        let on = match tokens.next() {
+            Some("on") => true,
+            Some("off") => false,
+            _ => return Err(anyhow!("Invalid debug value!")),
+        };
+
+        self.interface.handle_command(EngineCommand::Debug(on));
+        Ok(())
+    }
+
+    /// tt command.
+    /// Usage: tt stats
+    pub fn cmd_tt(&self, tokens: &mut SplitWhitespace) -> Result<()> {
+        match tokens.next() {
+            Some("stats") => self.interface.handle_command(EngineCommand::TTStats),
+            _ => return Err(anyhow!("Unknown tt command!")),
+        }
+
+        Ok(())
+    }
+
     /// move command.
     pub fn cmd_move(&self, tokens: &mut SplitWhitespace) -> Result<()> {
         let m = tokens.collect::<Vec<&str>>().join(" ");
@@ -165,3 +217,60 @@ impl UCIReader {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::UCIReader;
+
+    #[test]
+    fn test_malformed_input() {
+        const LINES: &[&str] = &[
+            "",
+            "   ",
+            "notacommand",
+            "go depth abc",
+            "go depth",
+            "go wtime 1000",
+            "go nodestime 0 wtime 10 btime 10",
+            "position",
+            "position fen",
+            "position fen 8/8/8/8 w",
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 x",
+            "position frc 960",
+            "position frc abc",
+            "setoption",
+            "setoption name",
+            "setoption value 1",
+            "perft",
+            "perft 0",
+            "perftmp -1",
+            "bench abc",
+            "debug maybe",
+            "tt",
+        ];
+
+        let reader = UCIReader::default();
+        for line in LINES {
+            assert!(reader.parse_command(line).is_err(), "{line:?} should be rejected");
+            assert!(!reader.handle_line(line), "{line:?} should not quit");
+        }
+
+        assert!(matches!(reader.parse_command("isready"), Ok(false)));
+        assert!(reader.handle_line("quit"));
+    }
+
+    #[test]
+    fn test_isready_during_search() {
+        let reader = UCIReader::default();
+
+        // isready is answered straight away, without waiting for the search to finish.
+        assert!(!reader.handle_line("go infinite"));
+        assert!(!reader.handle_line("isready"));
+        assert!(reader.interface.is_searching());
+
+        // stop only returns once the bestmove has been printed, so a following readyok comes after it.
+        assert!(!reader.handle_line("stop"));
+        assert!(!reader.interface.is_searching());
+        assert!(!reader.handle_line("isready"));
+    }
+}
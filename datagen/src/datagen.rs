@@ -19,7 +19,7 @@ use fastrand::Rng;
 use humantime::format_duration;
 
 use crate::{
-    formats::{marlinfmt::MarlinWDL, virifmt::ViriFmt},
+    formats::{marlinfmt::MarlinWDL, textfmt::TextFmt, virifmt::ViriFmt},
     genfens::gen_random_position,
 };
 
@@ -52,6 +52,10 @@ pub struct DataGenOpts {
     /// Whether to generate DFRC data.
     #[arg(long, short = 'd', default_value_t = false)]
     pub dfrc: bool,
+
+    /// Write plain text `fen | score | result` lines instead of viriformat.
+    #[arg(long, default_value_t = false)]
+    pub text: bool,
 }
 
 const PRINT_INTERVAL: usize = 64;
@@ -116,6 +120,7 @@ pub fn run_datagen(opts: DataGenOpts) -> anyhow::Result<()> {
     println!("Nodes per move: {}", opts.search_nodes);
     println!("Tablebase:      {tb_str}");
     println!("Gen DFRC data:  {}", opts.dfrc);
+    println!("Text output:    {}", opts.text);
     println!("Saving to:      {}", outdir.display());
 
     let tc = TimeControl::FixedNodes(opts.search_nodes);
@@ -126,7 +131,7 @@ pub fn run_datagen(opts: DataGenOpts) -> anyhow::Result<()> {
         let mut handles = Vec::new();
         for thread_id in 0..opts.threads {
             let outdir_ref = &outdir;
-            handles.push(s.spawn(move || thread_gen_data(thread_id, seed, outdir_ref, games_per_thread, tb, tc, opts.dfrc)));
+            handles.push(s.spawn(move || thread_gen_data(thread_id, seed, outdir_ref, games_per_thread, tb, tc, opts.dfrc, opts.text)));
         }
         for handle in handles {
             handle.join().expect("thread panicked")?;
@@ -190,6 +195,7 @@ fn update_adj_counters(abs_score: Eval, win: &mut usize, draw: &mut usize) {
 }
 
 /// Generate data on this thread.
+#[allow(clippy::too_many_arguments)]
 fn thread_gen_data(
     thread_id: usize,
     seed: u64,
@@ -198,6 +204,7 @@ fn thread_gen_data(
     tb: SyzygyTB,
     tc: TimeControl,
     dfrc: bool,
+    text: bool,
 ) -> anyhow::Result<()> {
     let mut pos = Position::default();
     let mut rng = Rng::with_seed(seed + thread_id as u64);
@@ -206,7 +213,7 @@ fn thread_gen_data(
     let tc_verification = TimeControl::FixedNodes(VERIFICATION_NODES);
     let mut thread = Thread::placeholder();
 
-    let outfile = File::create(outdir.join(format!("data_{thread_id}.vf")))?;
+    let outfile = File::create(outdir.join(format!("data_{thread_id}.{}", if text { "txt" } else { "vf" })))?;
     let mut writer = BufWriter::new(outfile);
 
     let mut positions = 0;
@@ -234,6 +241,7 @@ fn thread_gen_data(
         }
 
         let mut compressed_pos = ViriFmt::new(&pos.board);
+        let mut text_pos = TextFmt::default();
         let should_adj = rng.f32() < ADJ_RATIO;
         let mut win_adj_counter = 0;
         let mut draw_adj_counter = 0;
@@ -269,7 +277,11 @@ fn thread_gen_data(
 
             let best_move = thread.best_move();
             let score = thread.eval;
-            compressed_pos.push(&pos.board, best_move, score);
+            if text {
+                text_pos.push(&pos.board, best_move, score);
+            } else {
+                compressed_pos.push(&pos.board, best_move, score);
+            }
 
             // If either side proves mate, terminate immediately.
             if score.is_terminal() {
@@ -293,10 +305,15 @@ fn thread_gen_data(
             pos.make_move(best_move, &mut thread);
         };
 
-        positions += compressed_pos.moves.len();
-
-        compressed_pos.finish(result);
-        compressed_pos.write(&mut writer)?;
+        if text {
+            positions += text_pos.positions.len();
+            text_pos.finish(result);
+            text_pos.write(&mut writer)?;
+        } else {
+            positions += compressed_pos.moves.len();
+            compressed_pos.finish(result);
+            compressed_pos.write(&mut writer)?;
+        }
     }
 
     writer.flush()?;
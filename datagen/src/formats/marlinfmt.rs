@@ -125,6 +125,7 @@ impl MarlinFmt {
 
         b.update_masks(&mut state);
 
+        state.hash.material_key = b.calc_material_key();
         b.state = state;
         (b, i16::from_le(self.eval), self.wdl)
     }
@@ -1,2 +1,3 @@
 pub mod marlinfmt;
+pub mod textfmt;
 pub mod virifmt;
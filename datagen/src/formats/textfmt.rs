@@ -0,0 +1,48 @@
+use std::io::Write;
+
+use chess::types::{board::Board, color::Color, eval::Eval, moves::Move};
+
+use crate::formats::marlinfmt::MarlinWDL;
+
+/// Plain text format.
+///
+/// Each position is written on its own line as `fen | score | result`,
+/// where the score is white relative and the result is 1.0, 0.5 or 0.0 from white's perspective.
+#[derive(Debug, Default)]
+pub struct TextFmt {
+    pub positions: Vec<(String, i16)>,
+    pub wdl: MarlinWDL,
+}
+
+impl TextFmt {
+    /// Add a searched position.
+    /// Positions in check or with a noisy best move are skipped, as their scores are not quiet.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn push(&mut self, b: &Board, m: Move, e: Eval) {
+        if b.in_check() || m.flag().is_noisy() {
+            return;
+        }
+
+        // Change to white relative eval.
+        let s = if b.stm == Color::White { e } else { -e };
+        self.positions.push((b.to_fen(), s.0 as i16));
+    }
+
+    pub const fn finish(&mut self, wdl: MarlinWDL) {
+        self.wdl = wdl;
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let result = match self.wdl {
+            MarlinWDL::Win => "1.0",
+            MarlinWDL::Draw => "0.5",
+            MarlinWDL::Loss => "0.0",
+        };
+
+        for (fen, score) in &self.positions {
+            writeln!(writer, "{fen} | {score} | {result}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use datagen::{
     datagen::{DataGenOpts, run_datagen},
     genfens::genfens,
 };
-use engine::bench::run_bench;
+use engine::bench::{BenchOpts, run_bench};
 
 #[derive(Parser, Debug)]
 #[command(name = "ven-datagen")]
@@ -36,7 +36,7 @@ fn main() {
             genfens(amount, seed);
             Ok(())
         }
-        Command::Bench => run_bench(None),
+        Command::Bench => run_bench(BenchOpts::default()),
     };
 
     if let Err(e) = result {
@@ -1,46 +1,75 @@
-use std::{path::PathBuf, time::Instant};
+use std::{
+    path::PathBuf,
+    sync::{Arc, atomic::AtomicBool},
+    time::Instant,
+};
 
+use chess::types::Depth;
 #[cfg(feature = "nnz_logging")]
 use nnue::inference::sparse::NNZ_TRACKER;
 use utils::parse::parse_file_ignore_hash;
 
-use crate::{position::Position, tb::probe::SyzygyTB, threading::thread::Thread, time_management::timecontrol::TimeControl, tt::table::TT};
+use crate::{
+    position::Position, tb::probe::SyzygyTB, threading::threadpool::ThreadPool, time_management::timecontrol::TimeControl, tt::table::TT,
+};
 
 // NOTE:  Make sure that bench depth is at least as high as the highest of any min depths in tuning.
 #[cfg(not(feature = "nnz_logging"))]
-const BENCH_DEPTH: i16 = 14;
+pub const BENCH_DEPTH: Depth = 14;
 
 // Use a lower depth if we just want to record neuron coactivations.
 #[cfg(feature = "nnz_logging")]
-const BENCH_DEPTH: i16 = 10;
+pub const BENCH_DEPTH: Depth = 10;
+
+/// Hash size in MB used for each bench position by default.
+pub const BENCH_HASH: usize = TT::DEFAULT_SIZE_MB;
+
+/// Options for running a bench.
+#[derive(Clone, Debug)]
+pub struct BenchOpts {
+    pub depth: Depth,
+    pub hash_mb: usize,
+    pub threads: usize,
+    pub epd_path: Option<PathBuf>,
+}
+
+impl Default for BenchOpts {
+    fn default() -> Self {
+        Self { depth: BENCH_DEPTH, hash_mb: BENCH_HASH, threads: 1, epd_path: None }
+    }
+}
 
 /// Runs a benchmark of the engine on a number of positions.
-/// # Panics
-///     Shouldn't panic, all FENs are valid.
+/// # Errors
+///     Errors if the EPD file can't be read or one of its positions is invalid.
 #[allow(clippy::cast_possible_truncation)]
-pub fn run_bench(epd_path: Option<PathBuf>) -> anyhow::Result<()> {
+pub fn run_bench(opts: BenchOpts) -> anyhow::Result<()> {
     let mut total_nodes = 0;
     let mut total_time = 0;
 
-    let fens = if let Some(p) = epd_path {
+    let fens = if let Some(p) = opts.epd_path {
         parse_file_ignore_hash(p)?
     } else {
         FENS.iter().map(|&s| s.to_string()).collect()
     };
 
     for fen in fens {
-        let tt = TT::default();
+        let tt = TT::with_size(opts.hash_mb);
         let tb = SyzygyTB::default();
-        let mut pos: Position = format!("fen {fen}").parse().unwrap();
-        let mut thread = Thread::from_tc(TimeControl::FixedDepth(BENCH_DEPTH), pos.stm());
+        let mut pos = Position::default();
+        pos.set_from_epd(&fen).map_err(anyhow::Error::msg)?;
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        pool.resize(opts.threads.max(1) - 1);
+        pool.silent = true;
 
         let start = Instant::now();
-        pos.iterative_deepening::<false>(&mut thread, &tt, &tb);
+        pool.go(&mut pos, TimeControl::FixedDepth(opts.depth), &tt, &tb);
 
+        let nodes = pool.main.nodes + pool.workers.iter().map(|t| t.nodes).sum::<u64>();
         total_time += start.elapsed().as_micros();
-        total_nodes += thread.nodes;
+        total_nodes += nodes;
 
-        println!("{fen:<90} | {:>10}", thread.nodes);
+        println!("{fen:<90} | {nodes:>10}");
     }
 
     println!("{total_nodes} nodes {} nps", total_nodes * 1_000_000 / (total_time as u64).max(1));
@@ -51,6 +80,37 @@ pub fn run_bench(epd_path: Option<PathBuf>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Number of full evaluations to run per position in the eval bench.
+pub const EVAL_BENCH_ITERS: usize = 10_000;
+
+/// Runs a benchmark of raw NNUE evaluation throughput on the bench positions.
+/// Each evaluation is done from a full refresh, so this is independent of search.
+/// # Panics
+///     Shouldn't panic, all FENs are valid.
+#[allow(clippy::cast_possible_truncation)]
+pub fn run_eval_bench(iters: usize) -> u64 {
+    let mut total_evals = 0;
+    let mut total_time = 0;
+
+    for fen in FENS {
+        let mut pos: Position = format!("fen {fen}").parse().unwrap();
+
+        let start = Instant::now();
+        for _ in 0..iters {
+            pos.reinit_nnue();
+            std::hint::black_box(pos.nnue.evaluate(&pos.board));
+        }
+
+        total_time += start.elapsed().as_micros();
+        total_evals += iters as u64;
+    }
+
+    let eps = total_evals * 1_000_000 / (total_time as u64).max(1);
+    println!("{total_evals} evals {eps} evals/s");
+
+    eps
+}
+
 const FENS: &[&str] = &[
     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
     "rn2kbnr/p1q1ppp1/1ppp3p/8/4B1b1/2P4P/PPQPPP2/RNB1K1NR w KQkq - 0 1",
@@ -95,3 +155,13 @@ const FENS: &[&str] = &[
     "nqbnrkrb/pppppppp/8/8/8/8/PPPPPPPP/NQBNRKRB w KQkq - 0 1",
     "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1",
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_bench() {
+        assert!(run_eval_bench(4) > 0);
+    }
+}
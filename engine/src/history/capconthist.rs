@@ -0,0 +1,51 @@
+use chess::movegen::MoveList;
+use chess::types::{board::Board, moves::Move, piece::Piece, square::Square};
+use utils::memory::boxed_zeroed;
+
+use crate::history::{HistEntry, conthist::PieceTo, noisyhist::CAP_HIST_MAX};
+
+/// Capture continuation history.
+///
+/// This records the history of combinations of the current capture and the previous move,
+/// in order to help order recapture sequences.
+/// We can use [`Piece::NUM`] - 1 because kings cannot be captured by a legal move.
+#[derive(Clone, Debug)]
+pub struct CapContHist(Box<[[[HistEntry; Piece::NUM - 1]; Square::NUM]; PieceTo::NUM]>);
+
+// TODO: add tunable history defaults.
+impl Default for CapContHist {
+    fn default() -> Self {
+        Self(boxed_zeroed())
+    }
+}
+
+impl CapContHist {
+    /// The index into this history.
+    /// [pieceto][to][captured]
+    fn idx(b: &Board, m: Move, pt: PieceTo) -> (usize, usize, usize) {
+        (pt.idx(), m.dst().idx(), b.captured(m).pt().idx())
+    }
+
+    /// Add a bonus to the given capture and previous move.
+    fn add_bonus(&mut self, b: &Board, m: Move, pt: PieceTo, bonus: i16) {
+        let i = Self::idx(b, m, pt);
+        self.0[i.0][i.1][i.2].gravity::<CAP_HIST_MAX>(bonus);
+    }
+
+    /// Get a bonus for the given capture and previous move.
+    pub fn get_bonus(&self, b: &Board, m: Move, pt: PieceTo) -> i32 {
+        let i = Self::idx(b, m, pt);
+        i32::from(self.0[i.0][i.1][i.2].0)
+    }
+
+    /// Update the history with the given moves.
+    pub fn update(&mut self, b: &Board, best: Move, pt: PieceTo, captures: &MoveList, bonus: i16, malus: i16) {
+        for m in captures {
+            self.add_bonus(b, *m, pt, -malus);
+        }
+
+        if best.flag().is_cap() {
+            self.add_bonus(b, best, pt, bonus);
+        }
+    }
+}
@@ -4,10 +4,31 @@ use chess::{
 };
 use utils::memory::boxed_zeroed;
 
-use crate::history::HistEntry;
+use crate::{
+    history::HistEntry,
+    tunables::params::tunables::{ch_scale_0, ch_scale_1, ch_scale_2, ch_scale_3},
+};
 
 const CONT_HIST_MAX: i32 = 16384;
-pub const CONT_NUM: usize = 2;
+
+/// How many plies back each continuation history looks.
+#[cfg(not(feature = "deep_conthist"))]
+pub const CONT_PLIES: [usize; 2] = [1, 2];
+#[cfg(feature = "deep_conthist")]
+pub const CONT_PLIES: [usize; 4] = [1, 2, 4, 6];
+
+pub const CONT_NUM: usize = CONT_PLIES.len();
+
+/// The weight of the `i`th continuation history. (scaled up x1024).
+pub fn ch_scale(i: usize) -> i32 {
+    match i {
+        0 => ch_scale_0(),
+        1 => ch_scale_1(),
+        2 => ch_scale_2(),
+        3 => ch_scale_3(),
+        _ => unreachable!(),
+    }
+}
 
 /// Continuation history.
 ///
@@ -30,7 +51,7 @@ impl ContHist {
     }
 
     /// Add a bonus to the given move pair.
-    const fn add_bonus(&mut self, m: Move, pt: PieceTo, bonus: i16) {
+    pub const fn add_bonus(&mut self, m: Move, pt: PieceTo, bonus: i16) {
         let i = Self::idx(m, pt);
         self.0[i.0][i.1][i.2].gravity::<CONT_HIST_MAX>(bonus);
     }
@@ -1,7 +1,10 @@
 use chess::types::{Depth, color::Color, eval::Eval};
 use utils::memory::boxed_zeroed;
 
-use crate::history::HistEntry;
+use crate::{
+    history::HistEntry,
+    tunables::params::tunables::{hist_corr_bonus_base, hist_corr_bonus_depth},
+};
 
 const CORR_HIST_SIZE: usize = 32768;
 const CORR_HIST_MAX: i32 = 1024;
@@ -43,8 +46,42 @@ impl CorrHist {
 }
 
 /// Get the correction bonus for this eval difference at this depth.
+/// Deeper searches are more reliable, so the bonus grows faster than linearly with depth.
+/// This is computed in i64, as mate scores at high depths overflow i32.
 #[allow(clippy::cast_possible_truncation)]
 pub fn correction_bonus(best: Eval, stat: Eval, depth: Depth) -> i16 {
-    const MAX_DIFF: i32 = CORR_HIST_MAX / 4;
-    ((best.0 - stat.0) * depth as i32 / 8).clamp(-MAX_DIFF, MAX_DIFF) as i16
+    const MAX_DIFF: i64 = CORR_HIST_MAX as i64 / 4;
+    let d = i64::from(depth.max(0));
+    let scale = d * (i64::from(hist_corr_bonus_base()) + i64::from(hist_corr_bonus_depth()) * d);
+    ((i64::from(best.0) - i64::from(stat.0)) * scale / 1024).clamp(-MAX_DIFF, MAX_DIFF) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::defs::MAX_PLY;
+
+    use super::*;
+
+    #[test]
+    fn test_deep_corrections_dominate() {
+        let (best, stat) = (Eval(10), Eval(0));
+        let shallow = correction_bonus(best, stat, 2);
+        let deep = correction_bonus(best, stat, 10);
+        assert!(deep > 5 * shallow);
+        assert!(correction_bonus(Eval(10_000), stat, 20) <= (CORR_HIST_MAX / 4) as i16);
+
+        // A shallow update in one direction is overwritten by a deep one in the other.
+        let mut h = CorrHist::default();
+        h.add_bonus(0, Color::White, correction_bonus(Eval(-50), stat, 2));
+        h.add_bonus(0, Color::White, correction_bonus(Eval(50), stat, 10));
+        assert!(h.get_bonus(0, Color::White) > 0);
+    }
+
+    #[test]
+    fn test_bonus_no_overflow() {
+        let max = (CORR_HIST_MAX / 4) as i16;
+        let mate = Eval::search_mate_in(1);
+        assert_eq!(correction_bonus(mate, -Eval::INFINITY, (MAX_PLY - 1) as Depth), max);
+        assert_eq!(correction_bonus(-mate, Eval::INFINITY, (MAX_PLY - 1) as Depth), -max);
+    }
 }
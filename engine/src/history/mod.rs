@@ -1,3 +1,4 @@
+pub mod capconthist;
 pub mod conthist;
 pub mod corrhist;
 pub mod noisyhist;
@@ -19,10 +20,21 @@ impl HistEntry {
     /// <https://www.chessprogramming.org/History_Heuristic>
     #[allow(clippy::cast_possible_truncation)]
     pub const fn gravity<const MAX: i32>(&mut self, bonus: i16) {
+        const { assert!(MAX > 0, "History max must be positive!") };
+
         // Do calculations as i32
         let x = self.0 as i32;
         let b = bonus as i32;
-        self.0 += (b - x * b.abs() / MAX) as i16;
+        let v = x + b - x * b.abs() / MAX;
+
+        // Clamp to i16 range so extreme values can't overflow.
+        self.0 = if v > i16::MAX as i32 {
+            i16::MAX
+        } else if v < i16::MIN as i32 {
+            i16::MIN
+        } else {
+            v as i16
+        };
     }
 }
 
@@ -33,3 +45,59 @@ pub fn hist_delta(depth: Depth) -> (i16, i16) {
     // SAFETY: (bonus|malus)_max are both in i16 range.
     (bonus as i16, malus as i16)
 }
+
+#[cfg(test)]
+mod tests {
+    use utils::rng::next_rng;
+
+    use super::*;
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_gravity_no_overflow() {
+        let mut rng = 0x1234_5678_9ABC_DEF0;
+
+        for start in [i16::MIN, -16384, -1, 0, 1, 16384, i16::MAX] {
+            let mut small = HistEntry(start);
+            let mut large = HistEntry(start);
+
+            for _ in 0..100_000 {
+                rng = next_rng(rng);
+                let bonus = rng as i16;
+
+                small.gravity::<1024>(bonus);
+                large.gravity::<{ i16::MAX as i32 }>(bonus);
+            }
+        }
+
+        // Results outside of the i16 range saturate.
+        let mut e = HistEntry(i16::MAX);
+        e.gravity::<1024>(i16::MIN);
+        assert_eq!(e.0, i16::MIN);
+
+        let mut e = HistEntry(i16::MIN);
+        e.gravity::<1024>(i16::MAX);
+        assert_eq!(e.0, i16::MAX);
+
+        // Results inside the range are unaffected: 512 + 100 - 512 * 100 / 1024.
+        let mut e = HistEntry(512);
+        e.gravity::<1024>(100);
+        assert_eq!(e.0, 562);
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn test_gravity_stays_in_bounds() {
+        let mut rng = 0x0FED_CBA9_8765_4321;
+        let mut e = HistEntry(0);
+
+        // As long as the bonuses are within MAX, entries must never leave -MAX..=MAX.
+        for _ in 0..100_000 {
+            rng = next_rng(rng);
+            let bonus = (rng % 2049) as i16 - 1024;
+
+            e.gravity::<1024>(bonus);
+            assert!((-1024..=1024).contains(&e.0));
+        }
+    }
+}
@@ -30,7 +30,7 @@ impl QuietHist {
     }
 
     /// Add a bonus to the given move.
-    const fn add_bonus(&mut self, c: Color, m: Move, bonus: i16) {
+    pub const fn add_bonus(&mut self, c: Color, m: Move, bonus: i16) {
         let i = Self::idx(c, m);
         self.0[i.0][i.1][i.2].gravity::<QUIET_MAX>(bonus);
     }
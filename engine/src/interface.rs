@@ -1,6 +1,7 @@
 use std::{
+    num::NonZeroUsize,
     sync::{
-        Arc,
+        Arc, Condvar, Mutex,
         atomic::{AtomicBool, Ordering},
         mpsc,
     },
@@ -26,15 +27,89 @@ pub struct Engine {
     pub pool: ThreadPool,
     pub tt: TT,
     pub tb: SyzygyTB,
+    searching: Arc<Searching>,
 }
 
 /// Engine interface.
 /// This is how to communicate with the engine.
 pub struct EngineInterface {
     stop: Arc<AtomicBool>,
+    searching: Arc<Searching>,
     tx: mpsc::Sender<EngineCommand>,
 }
 
+/// Whether the engine is searching (or has a search queued), shared between the engine and its interface.
+/// The interface waits on this to know when the best move has been printed.
+#[derive(Default)]
+struct Searching {
+    flag: Mutex<bool>,
+    done: Condvar,
+}
+
+impl Searching {
+    /// Whether a search is running or queued.
+    fn get(&self) -> bool {
+        *self.flag.lock().unwrap()
+    }
+
+    /// Mark a search as queued.
+    fn start(&self) {
+        *self.flag.lock().unwrap() = true;
+    }
+
+    /// Mark the search as finished, clear the stop flag and wake anyone waiting on it.
+    /// The stop flag is only raised while holding the lock, so it can't leak into the next search.
+    fn finish(&self, stop: &AtomicBool) {
+        let mut flag = self.flag.lock().unwrap();
+        stop.store(false, Ordering::SeqCst);
+        *flag = false;
+        self.done.notify_all();
+    }
+
+    /// Raise the stop flag, and block until the search has finished.
+    fn stop(&self, stop: &AtomicBool) {
+        let mut flag = self.flag.lock().unwrap();
+        while *flag {
+            stop.store(true, Ordering::SeqCst);
+            flag = self.done.wait(flag).unwrap();
+        }
+    }
+}
+
+/// Marks the search as finished when the engine thread exits, so the interface never waits on a dead engine.
+struct FinishOnExit(Arc<Searching>, Arc<AtomicBool>);
+
+impl Drop for FinishOnExit {
+    fn drop(&mut self) {
+        self.0.finish(&self.1);
+    }
+}
+
+/// Options that resize shared search state, so cannot be applied during a search.
+const DEFERRED_OPTS: &[&str] = &["Hash", "Threads"];
+
+/// All UCI options supported by the engine, as (name, type) pairs.
+/// This is what the `uci` command advertises, so every option here must be handled by setoption.
+/// The thread limit depends on the machine, so this can't be a constant.
+pub fn uci_option_list() -> Vec<(&'static str, String)> {
+    vec![
+        ("UCI_Chess960", "type check default false".to_owned()),
+        ("Threads", format!("type spin default 1 min 1 max {}", Engine::max_threads())),
+        ("Hash", "type spin default 16 min 1 max 65536".to_owned()),
+        ("Deterministic", "type check default false".to_owned()),
+        ("Contempt", "type spin default 0 min -100 max 100".to_owned()),
+        ("Ponder", "type check default false".to_owned()),
+        ("Clear Hash", "type button".to_owned()),
+        #[cfg(feature = "syzygy")]
+        ("SyzygyPath", "type string default".to_owned()),
+    ]
+}
+
+/// The supported options in the format of the `uci` command.
+pub fn uci_options() -> String {
+    uci_option_list().iter().map(|(name, kind)| format!("option name {name} {kind}")).collect::<Vec<_>>().join("\n")
+}
+
 /// Engine command.
 /// List of all commands that the engine can be given.
 pub enum EngineCommand {
@@ -49,6 +124,8 @@ pub enum EngineCommand {
     Eval,
     Move(String),
     Undo,
+    Debug(bool),
+    TTStats,
 }
 
 /// Setup engine in new thread.
@@ -56,27 +133,64 @@ impl Default for EngineInterface {
     fn default() -> Self {
         let (tx, rx) = mpsc::channel();
         let stop = Arc::new(AtomicBool::new(false));
+        let searching = Arc::new(Searching::default());
         let pool_stop = stop.clone();
+        let engine_searching = searching.clone();
 
-        thread::spawn(move || Engine::run(rx, pool_stop));
+        thread::spawn(move || Engine::run(rx, pool_stop, engine_searching));
 
-        Self { stop, tx }
+        Self { stop, searching, tx }
     }
 }
 
 impl EngineInterface {
     pub fn handle_command(&self, command: EngineCommand) {
         match command {
-            EngineCommand::Stop => self.stop.store(true, Ordering::Relaxed),
-            cmd => self.tx.send(cmd).unwrap_or_else(|_| println!("Failed to send command!")),
+            EngineCommand::Stop => self.stop_search(),
+            cmd => {
+                match &cmd {
+                    EngineCommand::Go(_) => self.searching.start(),
+                    EngineCommand::SetOpt(n, _) if self.is_deferred(n) => {
+                        println!("info string {n} will be applied after the current search");
+                    }
+                    _ => (),
+                }
+
+                self.tx.send(cmd).unwrap_or_else(|_| println!("Failed to send command!"));
+            }
         }
     }
+
+    /// Whether the engine is currently searching (or has a search queued).
+    pub fn is_searching(&self) -> bool {
+        self.searching.get()
+    }
+
+    /// Stop the current search, and wait until the best move has been printed.
+    /// This means that any commands after a stop (e.g. isready) are always answered after the bestmove.
+    fn stop_search(&self) {
+        self.searching.stop(&self.stop);
+    }
+
+    /// Whether a change to the given option will be deferred until the current search ends.
+    /// Commands are handled in order, so the engine picks it up as soon as the search has finished.
+    pub fn is_deferred(&self, n: &str) -> bool {
+        self.is_searching() && DEFERRED_OPTS.contains(&n)
+    }
 }
 
 impl Engine {
+    /// The most search threads we allow.
+    /// Running more threads than there are hardware threads only slows the search down.
+    pub fn max_threads() -> usize {
+        thread::available_parallelism().map_or(1, NonZeroUsize::get)
+    }
+
     /// Run the engine.
-    fn run(rx: mpsc::Receiver<EngineCommand>, stop: Arc<AtomicBool>) {
-        let mut controller = Self { pos: Position::default(), pool: ThreadPool::new(stop), tt: TT::default(), tb: SyzygyTB::default() };
+    fn run(rx: mpsc::Receiver<EngineCommand>, stop: Arc<AtomicBool>, searching: Arc<Searching>) {
+        let _finish = FinishOnExit(searching.clone(), stop.clone());
+        let mut controller =
+            Self { pos: Position::default(), pool: ThreadPool::new(stop), tt: TT::default(), tb: SyzygyTB::default(), searching };
 
         for c in rx {
             controller.handle_command(c);
@@ -97,6 +211,8 @@ impl Engine {
             EngineCommand::Move(m)       => self.handle_move(&m),
             EngineCommand::Undo          => self.handle_undo(),
             EngineCommand::Print         => println!("{}", self.pos.board),
+            EngineCommand::Debug(v)      => self.pool.debug = v,
+            EngineCommand::TTStats       => println!("{}", self.tt.stats()),
 
             // Should have been handled already.
             EngineCommand::Stop          => unreachable!()
@@ -117,7 +233,10 @@ impl Engine {
     fn handle_go(&mut self, tc: TimeControl) {
         self.tt.increment_age();
         let bestmove = self.pool.go(&mut self.pos, tc, &self.tt, &self.tb);
-        println!("bestmove {}", bestmove.to_uci(&self.pos.board.castlingmask));
+        if !self.pool.silent {
+            self.pool.out.println(format_args!("bestmove {}", bestmove.to_uci(&self.pos.board.castlingmask)));
+        }
+        self.searching.finish(&self.pool.global_stop);
     }
 
     /// Handle perft command.
@@ -146,14 +265,33 @@ impl Engine {
 
     /// Handle setopt command.
     fn handle_setopt(&mut self, n: &str, v: &str) {
+        if self.set_option(n, v) {
+            return;
+        }
+
+        #[cfg(feature = "tune")]
+        if tunables::set_tunable(n, v).is_err() {
+            println!("Unsupported option: {n}!");
+        }
+
+        #[cfg(not(feature = "tune"))]
+        eprintln!("Unsupported option: {n}!");
+    }
+
+    /// Apply one of the options from [`uci_option_list`].
+    /// Returns false if the option is not supported. Invalid values for supported options are ignored.
+    fn set_option(&mut self, n: &str, v: &str) -> bool {
         match n {
-            "Threads" => {
-                if let Ok(size) = v.parse::<usize>()
-                    && size > 0
-                {
-                    self.pool.resize(size - 1);
+            "Threads" => match v.parse::<usize>() {
+                Ok(0) | Err(_) => println!("info string invalid thread count {v}"),
+                Ok(size) => {
+                    let max = Self::max_threads();
+                    if size > max {
+                        println!("info string only {max} hardware threads available, using {max} threads");
+                    }
+                    self.pool.resize(size.min(max) - 1);
                 }
-            }
+            },
 
             "Hash" => {
                 if let Ok(size) = v.parse::<usize>()
@@ -169,12 +307,23 @@ impl Engine {
                 }
             }
 
-            "Clear" => {
-                if v == "Hash" {
-                    self.tt.clear();
+            "Deterministic" => {
+                if let Ok(val) = v.parse::<bool>() {
+                    self.pool.deterministic = val;
+                }
+            }
+
+            "Contempt" => {
+                if let Ok(val) = v.parse::<i32>() {
+                    self.pool.contempt = val.clamp(-100, 100);
                 }
             }
 
+            // We don't think on the opponent's time, but GUIs look for this option before they let us.
+            "Ponder" => (),
+
+            "Clear Hash" => self.tt.clear(),
+
             "SyzygyPath" => {
                 if self.tb.init(v) {
                     println!("info string found syzygy tb at {v} ({}-man)", self.tb.max_pcs);
@@ -183,16 +332,10 @@ impl Engine {
                 }
             }
 
-            #[cfg(feature = "tune")]
-            _ => {
-                if tunables::set_tunable(n, v).is_err() {
-                    println!("Unsupported option: {n}!");
-                }
-            }
-
-            #[cfg(not(feature = "tune"))]
-            _ => eprintln!("Unsupported option: {n}!"),
+            _ => return false,
         }
+
+        true
     }
 
     /// Handle move command.
@@ -209,3 +352,93 @@ impl Engine {
         self.pos.undo_move(&mut Thread::placeholder());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_setopt_deferred_during_search() {
+        let interface = EngineInterface::default();
+        assert!(!interface.is_deferred("Hash"));
+
+        interface.handle_command(EngineCommand::Go(TimeControl::Infinite));
+        thread::sleep(Duration::from_millis(50));
+
+        // Resizing the hash mid-search should be deferred with a notice.
+        assert!(interface.is_deferred("Hash"));
+        assert!(!interface.is_deferred("UCI_Chess960"));
+        interface.handle_command(EngineCommand::SetOpt("Hash".to_owned(), "32".to_owned()));
+
+        interface.handle_command(EngineCommand::Stop);
+        assert!(!interface.is_deferred("Hash"));
+    }
+
+    #[test]
+    fn test_advertised_options_handled() {
+        let mut engine = Engine {
+            pos: Position::default(),
+            pool: ThreadPool::new(Arc::new(AtomicBool::new(false))),
+            tt: TT::default(),
+            tb: SyzygyTB::default(),
+            searching: Arc::new(Searching::default()),
+        };
+
+        for (name, kind) in uci_option_list() {
+            let default = kind.split_whitespace().skip_while(|&s| s != "default").nth(1).unwrap_or("");
+            assert!(engine.set_option(name, default), "{name} is advertised but not handled");
+        }
+
+        assert!(uci_options().lines().all(|l| l.starts_with("option name ")));
+        assert!(!engine.set_option("NotAnOption", "1"));
+    }
+
+    #[test]
+    fn test_threads_clamped() {
+        let mut engine = Engine {
+            pos: Position::default(),
+            pool: ThreadPool::new(Arc::new(AtomicBool::new(false))),
+            tt: TT::default(),
+            tb: SyzygyTB::default(),
+            searching: Arc::new(Searching::default()),
+        };
+
+        let max = Engine::max_threads();
+        engine.set_option("Threads", &(max + 100).to_string());
+        assert_eq!(engine.pool.workers.len() + 1, max);
+
+        // The advertised range must match what we actually allow.
+        assert!(uci_options().contains(&format!("option name Threads type spin default 1 min 1 max {max}")));
+
+        // Zero threads is rejected, and the current count kept rather than clamped to one.
+        engine.pool.resize(3);
+        engine.set_option("Threads", "0");
+        assert_eq!(engine.pool.workers.len(), 3);
+    }
+
+    #[test]
+    fn test_stop_before_search_starts() {
+        let interface = EngineInterface::default();
+
+        // The engine may not have picked up the go yet, but the stop must still end the search.
+        interface.handle_command(EngineCommand::Go(TimeControl::Infinite));
+        interface.handle_command(EngineCommand::Stop);
+        assert!(!interface.is_searching());
+    }
+
+    #[test]
+    fn test_stop_waits_for_bestmove() {
+        let interface = EngineInterface::default();
+
+        // An infinite search keeps running until it is stopped.
+        interface.handle_command(EngineCommand::Go(TimeControl::Infinite));
+        thread::sleep(Duration::from_millis(50));
+        assert!(interface.is_searching());
+
+        // Once stop returns, bestmove has been printed.
+        interface.handle_command(EngineCommand::Stop);
+        assert!(!interface.is_searching());
+    }
+}
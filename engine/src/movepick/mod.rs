@@ -42,6 +42,13 @@ pub enum MPStage {
     PcNoisyGen,
     PcNoisyAll,
     PcEnd,
+
+    // Root starts here.
+    RtTT,
+    RtShared,
+    RtGen,
+    RtAll,
+    RtEnd,
 }
 
 /// Search type.
@@ -51,12 +58,13 @@ pub enum SearchType {
     Pv,
     Qs,
     Pc,
+    Rt,
 }
 
 impl MPStage {
     /// Get the next move pick stage.
     pub fn next(self) -> Self {
-        assert!(!matches!(&self, MPStage::PvEnd | MPStage::QsEnd | MPStage::EvEnd | MPStage::PcEnd));
+        assert!(!matches!(&self, MPStage::PvEnd | MPStage::QsEnd | MPStage::EvEnd | MPStage::PcEnd | MPStage::RtEnd));
         unsafe { std::mem::transmute(self as u8 + 1) }
     }
 }
@@ -70,6 +78,7 @@ pub struct MovePicker {
 
     tt_move: Move,
     killer: Move,
+    shared: Move,
 
     see_threshold: Eval,
 
@@ -79,14 +88,12 @@ pub struct MovePicker {
 impl MovePicker {
     /// Construct a new move picker for the position.
     pub fn new(searchtype: SearchType, in_check: bool, tt_move: Move, see_threshold: Eval) -> Self {
-        let mut stage = if in_check {
-            MPStage::EvTT
-        } else {
-            match searchtype {
-                SearchType::Pv => MPStage::PvTT,
-                SearchType::Qs => MPStage::QsTT,
-                SearchType::Pc => MPStage::PcTT,
-            }
+        let mut stage = match searchtype {
+            SearchType::Rt => MPStage::RtTT,
+            _ if in_check => MPStage::EvTT,
+            SearchType::Pv => MPStage::PvTT,
+            SearchType::Qs => MPStage::QsTT,
+            SearchType::Pc => MPStage::PcTT,
         };
 
         let tt_move = tt_move.is_some_or(|| {
@@ -94,6 +101,33 @@ impl MovePicker {
             Move::NONE
         });
 
-        Self { stage, searchtype, tt_move, killer: Move::NONE, see_threshold, skip_quiets: false, move_list: MoveList::default() }
+        Self {
+            stage,
+            searchtype,
+            tt_move,
+            killer: Move::NONE,
+            shared: Move::NONE,
+            see_threshold,
+            skip_quiets: false,
+            move_list: MoveList::default(),
+        }
+    }
+
+    /// Try the given move straight after the TT move at the root.
+    /// This is used for the best move shared by the other threads, which may be stale.
+    pub const fn with_shared(mut self, m: Move) -> Self {
+        self.shared = m;
+        self
+    }
+
+    /// Whether all the good noisy moves have been picked, so the remaining moves may be SEE pruned.
+    /// Root moves ordered by node counts are not split into good and bad noisies, so they never are.
+    pub fn past_good_noisies(&self) -> bool {
+        self.stage > MPStage::PvNoisyWin && !(MPStage::RtTT..=MPStage::RtEnd).contains(&self.stage)
+    }
+
+    /// Whether the move has already been picked before the moves were generated.
+    fn is_picked(&self, m: Move) -> bool {
+        m == self.tt_move || m == self.killer || m == self.shared
     }
 }
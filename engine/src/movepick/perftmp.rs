@@ -82,4 +82,17 @@ mod tests {
             assert_eq!(nodes, *correct_count);
         }
     }
+
+    #[test]
+    fn test_perftmp_matches_perft() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "1rkr4/8/8/8/8/8/8/1RKR4 w KQkq - 0 1",
+        ] {
+            let mut pos: Position = format!("fen {fen}").parse().unwrap();
+            let expected = pos.board.perft::<false>(5);
+            assert_eq!(pos.perftmp::<false>(5), expected, "{fen}");
+        }
+    }
 }
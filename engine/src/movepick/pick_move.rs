@@ -4,10 +4,17 @@ use super::{MPStage, MovePicker};
 use crate::threading::thread::Thread;
 
 impl MovePicker {
+    /// Get the next move, along with its history score.
+    /// The history score is only computed here, so callers that don't need it should use [`Self::next`].
+    pub fn next_scored(&mut self, b: &Board, t: &Thread) -> Option<(Move, i32)> {
+        self.next(b, t).map(|m| (m, t.hist_score(b, m)))
+    }
+
+    /// Get the next move.
     pub fn next(&mut self, b: &Board, t: &Thread) -> Option<Move> {
         match self.stage {
             // Return TT move.
-            MPStage::PvTT | MPStage::QsTT | MPStage::EvTT => {
+            MPStage::PvTT | MPStage::QsTT | MPStage::EvTT | MPStage::RtTT => {
                 self.stage = self.stage.next();
                 return Some(self.tt_move);
             }
@@ -20,13 +27,33 @@ impl MovePicker {
                 }
             }
 
+            // Return the best move shared by the other threads, if it is legal here.
+            MPStage::RtShared => {
+                if !self.shared.is_none() && self.shared != self.tt_move && b.is_legal(self.shared) {
+                    self.stage = self.stage.next();
+                    return Some(self.shared);
+                }
+                self.shared = Move::NONE;
+            }
+
             // Generate and score noisies, and get ready to return noisy moves.
             MPStage::PvNoisyGen | MPStage::QsNoisyGen | MPStage::PcNoisyGen => {
                 self.gen_score_noisies(b, t);
             }
 
+            // Order root moves by the nodes spent on them in previous iterations.
+            // Before the first iteration has completed, we have no node counts, so use the usual ordering.
+            MPStage::RtGen => {
+                if t.depth == 0 {
+                    self.stage = if b.in_check() { MPStage::EvGen } else { MPStage::PvNoisyGen };
+                    return self.next(b, t);
+                }
+
+                self.gen_score_root(b, t);
+            }
+
             // Return all winning noisies.
-            MPStage::PvNoisyWin | MPStage::QsNoisyAll | MPStage::EvAll => {
+            MPStage::PvNoisyWin | MPStage::QsNoisyAll | MPStage::EvAll | MPStage::RtAll => {
                 if let Some(m) = self.move_list.next_good() {
                     return Some(m);
                 }
@@ -34,8 +61,10 @@ impl MovePicker {
 
             // Return killer move.
             MPStage::PvKiller => {
-                if let Some(km) = t.ss().killer
+                if !self.skip_quiets
+                    && let Some(km) = t.ss().killer
                     && km != self.tt_move
+                    && km != self.shared
                     && b.is_legal(km)
                 {
                     self.stage = self.stage.next();
@@ -45,13 +74,18 @@ impl MovePicker {
             }
 
             // Generate and score quiets.
+            // If we are skipping quiets, don't bother generating them at all.
             MPStage::PvQuietGen => {
-                if !self.skip_quiets {
-                    self.gen_score_quiets(b, t);
+                if self.skip_quiets {
+                    self.stage = MPStage::PvNoisyLoss;
+                    return self.next(b, t);
                 }
+
+                self.gen_score_quiets(b, t);
             }
 
             // Return all quiets.
+            // These may have been generated before we started skipping quiets, so check again.
             MPStage::PvQuietAll => {
                 if !self.skip_quiets
                     && let Some(m) = self.move_list.next_good()
@@ -73,16 +107,15 @@ impl MovePicker {
             }
 
             // Return all moves over the given SEE threshold.
+            // These have already been checked against the threshold during scoring.
             MPStage::PcNoisyAll => {
-                if let Some(m) = self.move_list.next_good()
-                    && b.see(m, self.see_threshold)
-                {
+                if let Some(m) = self.move_list.next_good() {
                     return Some(m);
                 }
             }
 
             // No more moves to play: end here.
-            MPStage::PvEnd | MPStage::QsEnd | MPStage::EvEnd | MPStage::PcEnd => {
+            MPStage::PvEnd | MPStage::QsEnd | MPStage::EvEnd | MPStage::PcEnd | MPStage::RtEnd => {
                 return None;
             }
         }
@@ -91,3 +124,131 @@ impl MovePicker {
         self.next(b, t)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chess::{
+        movegen::MoveList,
+        types::{eval::Eval, moves::MoveFlag, square::Square},
+    };
+
+    use super::*;
+    use crate::{movepick::SearchType, threading::pv::PVLine};
+
+    // White can play quiet moves, and has a losing capture (Qxd5).
+    const FEN: &str = "4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1";
+
+    fn collect(mp: &mut MovePicker, b: &Board, t: &Thread) -> Vec<Move> {
+        let mut moves = Vec::new();
+        while let Some(m) = mp.next(b, t) {
+            moves.push(m);
+        }
+        moves
+    }
+
+    #[test]
+    fn test_skip_quiets_before_gen() {
+        let b: Board = FEN.parse().unwrap();
+        let t = Thread::placeholder();
+        let qxd5 = b.find_move("d2d5").unwrap();
+
+        let mut mp = MovePicker::new(SearchType::Pv, false, Move::NONE, Eval::DRAW);
+        mp.skip_quiets = true;
+
+        let moves = collect(&mut mp, &b, &t);
+        assert!(moves.iter().all(|m| m.flag().is_noisy()));
+        assert!(moves.contains(&qxd5));
+    }
+
+    #[test]
+    fn test_skip_quiets_after_gen() {
+        let b: Board = FEN.parse().unwrap();
+        let t = Thread::placeholder();
+        let qxd5 = b.find_move("d2d5").unwrap();
+
+        // Take the first quiet move, then start skipping.
+        let mut mp = MovePicker::new(SearchType::Pv, false, Move::NONE, Eval::DRAW);
+        let first = mp.next(&b, &t).unwrap();
+        assert!(first.flag().is_quiet());
+        mp.skip_quiets = true;
+
+        let moves = collect(&mut mp, &b, &t);
+        assert_eq!(moves, vec![qxd5]);
+    }
+
+    #[test]
+    fn test_root_ordered_by_nodes() {
+        let b: Board = FEN.parse().unwrap();
+        let mut t = Thread::placeholder();
+        let qd3 = b.find_move("d2d3").unwrap();
+        let qxd5 = b.find_move("d2d5").unwrap();
+        let ke2 = b.find_move("e1e2").unwrap();
+
+        t.tm.update_nodes(qd3, 500);
+        t.tm.update_nodes(ke2, 1000);
+        t.tm.update_nodes(qxd5, 100);
+
+        // Before the first iteration completes, use the usual ordering.
+        let mut mp = MovePicker::new(SearchType::Rt, false, Move::NONE, Eval::DRAW);
+        assert_eq!(collect(&mut mp, &b, &t).last(), Some(&qxd5));
+
+        // Afterwards, the TT move comes first, then the rest by the number of nodes searched.
+        t.depth = 1;
+        let mut mp = MovePicker::new(SearchType::Rt, false, qd3, Eval::DRAW);
+        let moves = collect(&mut mp, &b, &t);
+        assert_eq!(moves[..3], [qd3, ke2, qxd5]);
+        assert_eq!(moves.len(), b.gen_moves().len());
+
+        // The best move from the last iteration stays first, even with fewer nodes.
+        t.pv.update(qxd5, &PVLine::default());
+        let mut mp = MovePicker::new(SearchType::Rt, false, Move::NONE, Eval::DRAW);
+        let moves = collect(&mut mp, &b, &t);
+        assert_eq!(moves[..3], [qxd5, ke2, qd3]);
+
+        // Root moves are not split into good and bad noisies, so none of them are SEE pruned.
+        assert!(!mp.past_good_noisies());
+    }
+
+    #[test]
+    fn test_root_shared_after_tt() {
+        let b: Board = FEN.parse().unwrap();
+        let t = Thread::placeholder();
+        let qd3 = b.find_move("d2d3").unwrap();
+        let ke2 = b.find_move("e1e2").unwrap();
+        let all = b.gen_moves().len();
+
+        // The TT move stays first, then the shared move, and neither is repeated.
+        let mut mp = MovePicker::new(SearchType::Rt, false, qd3, Eval::DRAW).with_shared(ke2);
+        let moves = collect(&mut mp, &b, &t);
+        assert_eq!(moves[..2], [qd3, ke2]);
+        assert_eq!(moves.len(), all);
+
+        // A shared move that matches the TT move, or is not legal here, is not tried separately.
+        let mut mp = MovePicker::new(SearchType::Rt, false, qd3, Eval::DRAW).with_shared(qd3);
+        assert_eq!(collect(&mut mp, &b, &t).len(), all);
+
+        let e7e5 = Move::new(Square::E7, Square::E5, MoveFlag::DoublePush);
+        let mut mp = MovePicker::new(SearchType::Rt, false, qd3, Eval::DRAW).with_shared(e7e5);
+        let moves = collect(&mut mp, &b, &t);
+        assert!(!moves.contains(&e7e5));
+        assert_eq!(moves.len(), all);
+    }
+
+    #[test]
+    fn test_next_scored_matches_hist_score() {
+        let b: Board = FEN.parse().unwrap();
+        let mut t = Thread::placeholder();
+        let qd3 = b.find_move("d2d3").unwrap();
+        let qxd5 = b.find_move("d2d5").unwrap();
+
+        t.update_history(qd3, 8, &b, &MoveList::new(), &MoveList::new());
+        t.update_history(qxd5, 8, &b, &MoveList::new(), &MoveList::new());
+        assert_ne!(t.hist_score(&b, qd3), 0);
+        assert_ne!(t.hist_score(&b, qxd5), 0);
+
+        let mut mp = MovePicker::new(SearchType::Pv, false, qd3, Eval::DRAW);
+        while let Some((m, h)) = mp.next_scored(&b, &t) {
+            assert_eq!(h, t.hist_score(&b, m));
+        }
+    }
+}
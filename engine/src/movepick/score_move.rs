@@ -1,3 +1,5 @@
+use std::cmp::Reverse;
+
 use chess::{
     movegen::{Allmv, Noisy, Quiet},
     types::{
@@ -11,7 +13,7 @@ use chess::{
 
 use super::{MovePicker, SearchType};
 use crate::{
-    history::noisyhist::CAP_HIST_MAX,
+    history::{conthist::ch_scale, noisyhist::CAP_HIST_MAX},
     threading::thread::Thread,
     tunables::params::tunables::{mp_gc_bonus, mp_givecheck_see},
 };
@@ -25,6 +27,18 @@ fn capture_value(b: &Board, m: Move) -> i32 {
 }
 
 impl MovePicker {
+    /// The SEE threshold a noisy move must pass to be considered good.
+    fn noisy_see_threshold(&self, score: i32) -> Eval {
+        match self.searchtype {
+            // In PV search, moves with a good history can afford to lose a little material.
+            // The root uses PV search ordering on the first iteration.
+            SearchType::Pv | SearchType::Rt => Eval(-score / 32),
+
+            // Qsearch and probcut use the threshold given on construction.
+            SearchType::Qs | SearchType::Pc => self.see_threshold,
+        }
+    }
+
     /// Generate all quiet moves and score them.
     pub fn gen_score_quiets(&mut self, b: &Board, t: &Thread) {
         let prev_piecetos = t.get_prev_piecetos();
@@ -48,16 +62,16 @@ impl MovePicker {
         // If kings are under threat, we would be in evasions.
 
         b.enumerate_moves::<_, Quiet>(|m| {
-            // We've already picked the TT move if it exists.
-            if m == self.tt_move || m == self.killer {
+            // We've already picked the TT move and killer if they exist.
+            if self.is_picked(m) {
                 return;
             }
 
             let mut score = t.hist_quiet.get_bonus(b.stm, m);
 
-            for (hist_cont, &pt_opt) in t.hist_conts.iter().zip(prev_piecetos.iter()) {
+            for (i, (hist_cont, &pt_opt)) in t.hist_conts.iter().zip(prev_piecetos.iter()).enumerate() {
                 if let Some(pt) = pt_opt {
-                    score += hist_cont.get_bonus(m, pt);
+                    score += hist_cont.get_bonus(m, pt) * ch_scale(i) / 1024;
                 }
             }
 
@@ -75,7 +89,7 @@ impl MovePicker {
     pub fn gen_score_noisies(&mut self, b: &Board, t: &Thread) {
         b.enumerate_moves::<_, Noisy>(|m| {
             // We've already picked the TT move if it exists.
-            if m == self.tt_move {
+            if self.is_picked(m) {
                 return;
             }
 
@@ -90,18 +104,49 @@ impl MovePicker {
                 f if f.is_underpromo() => 0,
 
                 // All other moves are captures, so this is safe.
-                _ => capture_value(b, m) + t.hist_noisy.get_bonus(b, m)
+                _ => capture_value(b, m) + t.hist_score(b, m)
             };
 
             // If this move doesn't pass the SEE test (or is an underpromotion),
             // move it back to the start with the other noisy moves.
-            let threshold = if self.searchtype == SearchType::Pv { Eval(-score / 32) } else { self.see_threshold };
-            if b.see(m, threshold) && !m.flag().is_underpromo() {
+            if (b.see(m, self.noisy_see_threshold(score)) && !m.flag().is_underpromo()) || self.is_qs_knight_check(b, m) {
                 self.move_list.push_good(m, score);
             } else {
                 self.move_list.push_bad(m, score);
             }
         });
+
+        // Quiet knight promotions are not noisy, so qsearch would never see them.
+        // Add the ones that give check, as these are often forks.
+        if self.searchtype == SearchType::Qs && !(b.pc_bb(b.stm, Piece::Pawn) & Bitboard::PR[b.stm.idx()]).is_empty() {
+            b.enumerate_moves::<_, Quiet>(|m| {
+                if !self.is_picked(m) && self.is_qs_knight_check(b, m) {
+                    self.move_list.push_good(m, MVV[Piece::Knight.idx()]);
+                }
+            });
+        }
+    }
+
+    /// Whether this is a knight promotion giving check that qsearch should look at.
+    fn is_qs_knight_check(&self, b: &Board, m: Move) -> bool {
+        self.searchtype == SearchType::Qs && matches!(m.flag(), MoveFlag::PromoN | MoveFlag::CPromoN) && b.gives_check(m)
+    }
+
+    /// Generate and score the root moves.
+    /// The best move from the last iteration comes first, then the rest by the number of nodes spent
+    /// searching them, as moves that took more effort are usually the more important ones to look at.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn gen_score_root(&mut self, b: &Board, t: &Thread) {
+        let best = t.best_move();
+        let mut moves = b.gen_moves();
+        moves.sort_by_key(|&m| (m != best, Reverse(t.tm.move_nodes(m))));
+
+        for (i, &m) in moves.iter().enumerate() {
+            // We've already picked the TT move if it exists.
+            if !self.is_picked(m) {
+                self.move_list.push_good(m, -(i as i32));
+            }
+        }
     }
 
     /// Generate all evasion moves and score them.
@@ -110,7 +155,7 @@ impl MovePicker {
 
         b.enumerate_moves::<_, Allmv>(|m| {
             // We've already picked the TT move if it exists.
-            if m == self.tt_move {
+            if self.is_picked(m) {
                 return;
             }
 
@@ -126,3 +171,119 @@ impl MovePicker {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chess::movegen::MoveList;
+
+    use super::*;
+    use crate::history::conthist::PieceTo;
+
+    // White can win a pawn with Nxe5, or lose the queen to the rook with Qxd5.
+    const FEN: &str = "3rk3/8/8/3pp3/8/5N2/3Q4/4K3 w - - 0 1";
+
+    fn split_noisies(searchtype: SearchType, see_threshold: Eval) -> (Vec<Move>, Vec<Move>) {
+        let b: Board = FEN.parse().unwrap();
+        let t = Thread::placeholder();
+        let mut mp = MovePicker::new(searchtype, false, Move::NONE, see_threshold);
+        mp.gen_score_noisies(&b, &t);
+
+        let mut good = Vec::new();
+        while let Some(m) = mp.move_list.next_good() {
+            good.push(m);
+        }
+
+        let mut bad = Vec::new();
+        while let Some(m) = mp.move_list.next_bad() {
+            bad.push(m);
+        }
+
+        (good, bad)
+    }
+
+    #[test]
+    fn test_probcut_good_noisies_pass_threshold() {
+        let b: Board = FEN.parse().unwrap();
+        let nxe5 = b.find_move("f3e5").unwrap();
+        let qxd5 = b.find_move("d2d5").unwrap();
+
+        // Winning a pawn passes a small threshold, losing the queen does not.
+        let (good, bad) = split_noisies(SearchType::Pc, Eval(50));
+        assert_eq!(good, vec![nxe5]);
+        assert_eq!(bad, vec![qxd5]);
+        assert!(good.iter().all(|&m| b.see(m, Eval(50))));
+
+        // Nothing wins a queen's worth of material.
+        let (good, bad) = split_noisies(SearchType::Pc, Eval(1000));
+        assert!(good.is_empty());
+        assert_eq!(bad.len(), 2);
+    }
+
+    #[test]
+    fn test_qsearch_good_noisies_pass_threshold() {
+        let b: Board = FEN.parse().unwrap();
+        let nxe5 = b.find_move("f3e5").unwrap();
+        let qxd5 = b.find_move("d2d5").unwrap();
+
+        let (good, bad) = split_noisies(SearchType::Qs, Eval::DRAW);
+        assert_eq!(good, vec![nxe5]);
+        assert_eq!(bad, vec![qxd5]);
+    }
+
+    #[test]
+    fn test_capture_continuation_orders_recapture() {
+        // Black has just taken on d5 with the knight. White can recapture, or take the bishop on h7.
+        let mut b: Board = "4k3/7b/5n2/3P4/8/2NB4/8/4K3 b - - 0 1".parse().unwrap();
+        let nxd5 = b.find_move("f6d5").unwrap();
+        let kd8 = b.find_move("e8d8").unwrap();
+        let (capture, other) = (PieceTo::from(&b, nxd5), PieceTo::from(&b, kd8));
+        b.make_move(nxd5);
+
+        let recapture = b.find_move("c3d5").unwrap();
+        let bxh7 = b.find_move("d3h7").unwrap();
+
+        let mut t = Thread::placeholder();
+        t.ply = 1;
+        t.hist_noisy.update(&b, bxh7, &MoveList::new(), 100, 0);
+        t.hist_capcont.update(&b, recapture, capture, &MoveList::new(), 1000, 0);
+
+        let first_noisy = |t: &Thread| {
+            let mut mp = MovePicker::new(SearchType::Pv, false, Move::NONE, Eval::DRAW);
+            mp.gen_score_noisies(&b, t);
+            mp.move_list.next_good().unwrap()
+        };
+
+        // The recapture is only preferred as a reply to the knight capture.
+        t.ss_at_mut(1).pieceto = Some(other);
+        assert_eq!(first_noisy(&t), bxh7);
+
+        t.ss_at_mut(1).pieceto = Some(capture);
+        assert_eq!(first_noisy(&t), recapture);
+    }
+
+    #[test]
+    fn test_qs_knight_promo_checks() {
+        // e8=N+ forks the king and queen, while e8=R is a quiet underpromotion without check.
+        let b: Board = "8/2q1P1k1/8/8/8/8/PP6/7K w - - 0 1".parse().unwrap();
+        let t = Thread::placeholder();
+        let e8n = b.find_move("e7e8n").unwrap();
+        let e8r = b.find_move("e7e8r").unwrap();
+
+        let good = |searchtype| {
+            let mut mp = MovePicker::new(searchtype, false, Move::NONE, Eval::DRAW);
+            mp.gen_score_noisies(&b, &t);
+
+            let mut good = Vec::new();
+            while let Some(m) = mp.move_list.next_good() {
+                good.push(m);
+            }
+            good
+        };
+
+        let qs = good(SearchType::Qs);
+        assert!(qs.contains(&e8n));
+        assert!(!qs.contains(&e8r));
+
+        assert!(!good(SearchType::Pv).contains(&e8n));
+    }
+}
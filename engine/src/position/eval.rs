@@ -1,11 +1,17 @@
-use chess::types::{eval::Eval, piece::Piece};
+use chess::types::{bitboard::Bitboard, color::Color, eval::Eval, piece::Piece};
 
 use super::Position;
 use crate::{
     threading::thread::Thread,
-    tunables::params::tunables::{ms_base, ms_bishop, ms_knight, ms_queen, ms_rook},
+    tunables::params::tunables::{fifty_move_scale, ms_base, ms_bishop, ms_knight, ms_queen, ms_rook, ocb_scale},
 };
 
+/// Number of halfmoves without progress before we start scaling the eval towards a draw.
+const FIFTY_MOVE_GRACE: usize = 20;
+
+/// Largest pawn difference for which an opposite colored bishop endgame is still considered drawish.
+const OCB_MAX_PAWN_DIFF: u32 = 2;
+
 /// Evaluation.
 impl Position {
     /// Evaluates the position using the NNUE.
@@ -17,16 +23,28 @@ impl Position {
         // material on the board when we might be winning.
         v = (v * self.material_scale()) / 1024;
 
+        // Opposite colored bishop endgames are very drawish, so pull the eval towards a draw.
+        if self.is_ocb_endgame() {
+            v = (v * ocb_scale()) / 1024;
+        }
+
         // Clamp eval to non-terminal range.
         v.clamp_to_nonterminal()
     }
 
-    /// Adjust the evaluation according to correction history and 50 move rule scaling.
+    /// Get the raw static eval, reusing the TT eval if there is a valid one.
+    pub fn raw_eval(&mut self, tt_eval: Eval) -> Eval {
+        if tt_eval.is_valid() { tt_eval } else { self.evaluate() }
+    }
+
+    /// Correct a raw static eval according to correction history and 50 move rule scaling.
+    /// This must be used for all raw evals, whether they are fresh or from the TT.
     #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
-    pub fn adjust_eval(&mut self, t: &mut Thread, mut v: Eval) -> Eval {
+    pub fn corrected_eval(&self, t: &Thread, mut v: Eval) -> Eval {
         // Scale down the eval if we're just shuffling pieces back and forth and not making
         // progress.
-        v = v * Eval(200 - self.board.state.halfmoves as i32) / Eval(200);
+        let h = self.board.state.halfmoves.saturating_sub(FIFTY_MOVE_GRACE) as i32;
+        v = v * Eval(fifty_move_scale() - h) / Eval(fifty_move_scale());
 
         // Add correction history.
         v += t.correction_score(&self.board);
@@ -47,4 +65,146 @@ impl Position {
 
         ms_base() + (total_material / 32)
     }
+
+    /// Whether the position is a drawish opposite colored bishop endgame.
+    /// Each side must only have a single bishop, on opposite colors, and pawns. Neither side may be
+    /// more than a couple of pawns up, as those endgames are often still winning.
+    fn is_ocb_endgame(&self) -> bool {
+        let b = &self.board;
+        let wb = b.pc_bb(Color::White, Piece::Bishop);
+        let bb = b.pc_bb(Color::Black, Piece::Bishop);
+        let non_pawn = b.occ() ^ b.p_bb(Piece::Pawn) ^ b.p_bb(Piece::King);
+        let wp = b.pc_bb(Color::White, Piece::Pawn).nbits();
+        let bp = b.pc_bb(Color::Black, Piece::Pawn).nbits();
+
+        wb.nbits() == 1
+            && bb.nbits() == 1
+            && non_pawn == (wb | bb)
+            && wp.abs_diff(bp) <= OCB_MAX_PAWN_DIFF
+            && (wb & Bitboard::WHITE_SQ).is_empty() != (bb & Bitboard::WHITE_SQ).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        search::pruning::can_apply_rfp,
+        tunables::params::tunables::{hist_corr_max, rfp_mult},
+    };
+
+    #[test]
+    fn test_rfp_uses_corrected_eval() {
+        let mut pos: Position = "startpos".parse().unwrap();
+        let mut t = Thread::placeholder();
+
+        // Push the correction history upwards for this position.
+        t.ss_mut().eval = Eval::DRAW;
+        for _ in 0..64 {
+            t.update_corrhist(&pos.board, Eval(1000), 8);
+        }
+
+        // A valid TT eval is reused as is.
+        let from_tt = pos.raw_eval(Eval(100));
+        assert_eq!(from_tt, Eval(100));
+
+        // Every table has saturated at 1024, so the correction is capped at hist_corr_max.
+        // There is no fifty move scaling at the start position.
+        let corrected = pos.corrected_eval(&t, from_tt);
+        assert_eq!(corrected, Eval(100 + hist_corr_max()));
+
+        // RFP should only trigger because of the correction.
+        let beta = Eval(100) - rfp_mult() + 1;
+        assert!(!can_apply_rfp(1, false, false, from_tt, beta));
+        assert!(can_apply_rfp(1, false, false, corrected, beta));
+    }
+
+    #[test]
+    fn test_fifty_move_scaling() {
+        let t = Thread::placeholder();
+        let fresh: Position = "fen 4k3/8/8/8/8/8/8/3QK3 w - - 2 60".parse().unwrap();
+        let stale: Position = "fen 4k3/8/8/8/8/8/8/3QK3 w - - 90 60".parse().unwrap();
+
+        let v = Eval(800);
+        assert_eq!(fresh.corrected_eval(&t, v), v);
+        assert!(stale.corrected_eval(&t, v) < fresh.corrected_eval(&t, v));
+        assert!(stale.corrected_eval(&t, -v) > fresh.corrected_eval(&t, -v));
+        assert!(stale.corrected_eval(&t, v) > Eval::DRAW);
+    }
+
+    #[test]
+    fn test_side_to_move_flipped_eval() {
+        // White is a queen up, so this is good for white whoever is to move.
+        let mut pos: Position = "fen 4k3/8/8/8/8/8/8/3QK3 w - - 0 1".parse().unwrap();
+        let white = pos.evaluate();
+        assert!(white > Eval::DRAW);
+
+        pos.board = pos.board.with_side_to_move_flipped().unwrap();
+        pos.reinit_nnue();
+        assert!(pos.evaluate() < Eval::DRAW);
+    }
+
+    #[test]
+    fn test_ocb_scaling() {
+        // Bishops on opposite colors with an extra pawn for white: a textbook draw.
+        let mut pos: Position = "fen 8/5k2/3b4/2P5/4P3/3K1B2/8/8 w - - 0 1".parse().unwrap();
+        assert!(pos.is_ocb_endgame());
+
+        let unscaled = (pos.nnue.evaluate(&pos.board) * pos.material_scale()) / 1024;
+        let scaled = pos.evaluate();
+        assert!(scaled.abs() < unscaled.abs());
+
+        // Same colored bishops should not be scaled.
+        let pos: Position = "fen 8/5k2/4b3/2P5/4P3/3K1B2/8/8 w - - 0 1".parse().unwrap();
+        assert!(!pos.is_ocb_endgame());
+
+        // Extra material should not be scaled.
+        let pos: Position = "fen 8/5k2/3b4/2P5/4P3/3K1B2/8/7R w - - 0 1".parse().unwrap();
+        assert!(!pos.is_ocb_endgame());
+
+        // Being several pawns up is usually still winning, so should not be scaled.
+        let pos: Position = "fen 8/5k2/3b4/2P5/4P3/3K1B2/PPP5/8 w - - 0 1".parse().unwrap();
+        assert!(!pos.is_ocb_endgame());
+    }
+
+    #[test]
+    fn test_incremental_updates_match_refresh() {
+        // With debug assertions on, every evaluation checks the accumulator against a full refresh.
+        let mut pos: Position = "fen r3k2r/8/8/8/3p4/8/4P3/R3K2R w KQkq - 0 1".parse().unwrap();
+        let mut t = Thread::placeholder();
+        pos.evaluate();
+
+        // Double push, en passant capture (Add1Sub2), then castling on both sides (Add2Sub2).
+        for uci in ["e2e4", "d4e3", "e1g1", "e8c8"] {
+            let m = pos.board.find_move(uci).unwrap();
+            pos.make_move(m, &mut t);
+            pos.evaluate();
+        }
+
+        // Unwinding should land back on accumulators that are still correct.
+        for _ in 0..4 {
+            pos.undo_move(&mut t);
+            pos.evaluate();
+        }
+    }
+
+    #[test]
+    fn test_evaluate_fresh_matches_incremental() {
+        let mut pos: Position = "kiwipete".parse().unwrap();
+        let mut t = Thread::placeholder();
+
+        for uci in ["e1g1", "h3g2", "e5f7", "g2f1q", "g1f1", "e7f7"] {
+            let m = pos.board.find_move(uci).unwrap();
+            pos.make_move(m, &mut t);
+
+            let fresh = pos.nnue.evaluate_fresh(&pos.board);
+            assert_eq!(pos.nnue.evaluate(&pos.board), fresh);
+        }
+
+        // A fresh evaluation of another board does not disturb the stack.
+        let other = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+        let before = pos.nnue.evaluate(&pos.board);
+        pos.nnue.evaluate_fresh(&other);
+        assert_eq!(pos.nnue.evaluate(&pos.board), before);
+    }
 }
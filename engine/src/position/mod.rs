@@ -11,7 +11,7 @@ use crate::{history::conthist::PieceTo, threading::thread::Thread};
 #[derive(Clone)]
 pub struct Position {
     pub board: Board,
-    nnue: NNUE,
+    pub(crate) nnue: NNUE,
 }
 
 impl Default for Position {
@@ -46,7 +46,7 @@ impl std::str::FromStr for Position {
                     tokens.next().ok_or("Invalid FEN!")?;
                 }
 
-                fen.parse()?
+                Board::from_fen_lenient(fen)?
             }
 
             // FRC parsing.
@@ -59,6 +59,16 @@ impl std::str::FromStr for Position {
         // Move parsing.
         if let Some("moves") = tokens.next() {
             for move_str in tokens {
+                // Some tools send null moves. These can't be played while in check.
+                if move_str == "0000" {
+                    if board.in_check() {
+                        return Err("Invalid null move!");
+                    }
+
+                    board.make_null();
+                    continue;
+                }
+
                 let m = board.find_move(move_str);
 
                 match m {
@@ -89,6 +99,24 @@ impl Position {
         self.nnue.update_all(&self.board);
     }
 
+    /// Set the position from an EPD line.
+    /// Anything after the board fields (EPD operations, or datagen `| score | result`) is ignored,
+    /// and missing move counters default to "0 1".
+    pub fn set_from_epd(&mut self, line: &str) -> Result<(), &'static str> {
+        let mut fields = line.split(['|', ';']).next().unwrap_or_default().split_whitespace().take(6).collect::<Vec<&str>>();
+        if fields.len() < 4 {
+            return Err("Invalid EPD!");
+        }
+
+        let counters = fields[4..].iter().take_while(|f| f.parse::<u32>().is_ok()).count();
+        fields.truncate(4 + counters);
+        fields.extend(["0", "1"].iter().skip(counters));
+
+        self.board = Board::from_fen_lenient(&fields.join(" "))?;
+        self.reinit_nnue();
+        Ok(())
+    }
+
     /// Make a null move on the board on the given thread.
     pub fn make_move(&mut self, m: Move, t: &mut Thread) {
         t.move_made(PieceTo::from(&self.board, m));
@@ -125,3 +153,32 @@ impl Position {
         self.board.stm
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_move_in_moves_list() {
+        let pos: Position = "startpos moves e2e4 0000 g1f3".parse().unwrap();
+        assert_eq!(pos.board.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        // The null move passes the turn, so black can't reply.
+        assert!("startpos moves e2e4 0000 e7e5".parse::<Position>().is_err());
+
+        // Only the literal null move is accepted, and never while in check.
+        assert!("startpos moves e2e4 000 g1f3".parse::<Position>().is_err());
+        assert!("startpos moves f2f3 e7e5 g2g4 d8h4 0000".parse::<Position>().is_err());
+    }
+
+    #[test]
+    fn test_legal_halfmove_clock_kept() {
+        // Past the 50 move rule, but still legal under the 75 move rule, so nothing should be repaired.
+        let pos: Position = "fen 8/8/8/r4k2/2K5/8/8/8 w - - 120 80".parse().unwrap();
+        assert_eq!(pos.board.state.halfmoves, 120);
+        assert_eq!(pos.board.to_fen(), "8/8/8/r4k2/2K5/8/8/8 w - - 120 80");
+
+        let pos: Position = "fen 8/8/8/r4k2/2K5/8/8/8 w - - 150 80".parse().unwrap();
+        assert!(pos.board.is_forced_draw());
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::atomic::Ordering;
 
-use chess::types::eval::Eval;
+use chess::types::{Depth, eval::Eval};
 
 use crate::{
     position::Position,
@@ -17,34 +17,66 @@ impl Position {
     /// Iterative deepening loop.
     /// Search at increasing depth until we should stop.
     pub fn iterative_deepening<const MAIN: bool>(&mut self, t: &mut Thread, tt: &TT, tb: &SyzygyTB) {
-        while t.should_start_iter() {
-            let eval = self.asp_window(t, tt, tb);
+        // Make sure we always have a legal move to play, even if we are stopped before the first
+        // iteration completes.
+        if let Some(&m) = self.board.gen_moves().first() {
+            t.pv.update(m, &PVLine::default());
+        }
 
-            // If search was stopped (time limit or manually), don't use the incomplete result.
-            if t.stop {
-                break;
-            }
+        while t.should_start_iter() && self.iterate::<MAIN>(t, tt, tb) {}
+    }
 
-            t.eval = eval;
-            t.depth += 1;
+    /// Search the next iteration.
+    /// Returns false if the search was stopped before the iteration finished.
+    fn iterate<const MAIN: bool>(&mut self, t: &mut Thread, tt: &TT, tb: &SyzygyTB) -> bool {
+        let eval = self.asp_window::<MAIN>(t, tt, tb);
+
+        // If search was stopped (time limit or manually), the iteration is incomplete.
+        // Don't count or share it as a completed depth.
+        if t.stop {
+            return false;
+        }
+
+        t.eval = eval;
+        t.depth += 1;
+        t.tm.update_best_move(t.best_move());
+        t.share_best_move();
+
+        if MAIN && !t.silent {
+            self.print_info(t, tt, t.depth, t.eval, "");
 
-            if MAIN {
-                println!(
-                    "info depth {} seldepth {} score {} hashfull {} tbhits {} {} {}",
-                    t.depth,
-                    t.seldepth,
-                    t.eval,
+            if t.debug {
+                t.out.println(format_args!(
+                    "info string hashfull {} time scale {:.2}",
                     tt.hashfull(),
-                    TB_HITS.load(Ordering::Relaxed),
-                    t.tm,
-                    t.pv.to_uci(&self.board.castlingmask)
-                );
+                    t.tm.time_scale(t.nodes, t.best_move())
+                ));
             }
         }
+
+        true
+    }
+
+    /// Print the current search info.
+    /// The bound is appended to the score when it is only a lower or upper bound.
+    fn print_info(&self, t: &mut Thread, tt: &TT, depth: Depth, score: Eval, bound: &str) {
+        t.tm.sync_nodes(t.nodes);
+
+        t.out.println(format_args!(
+            "info depth {} seldepth {} score {}{} hashfull {} tbhits {} {} {}",
+            depth,
+            t.seldepth,
+            score,
+            bound,
+            tt.hashfull(),
+            TB_HITS.load(Ordering::Relaxed),
+            t.tm,
+            t.pv.to_uci(&self.board)
+        ));
     }
 
     /// Aspiration window. Keep searching until we find something within the window.
-    fn asp_window(&mut self, t: &mut Thread, tt: &TT, tb: &SyzygyTB) -> Eval {
+    fn asp_window<const MAIN: bool>(&mut self, t: &mut Thread, tt: &TT, tb: &SyzygyTB) -> Eval {
         let mut pv = PVLine::default();
         let mut alpha = -Eval::INFINITY;
         let mut beta = Eval::INFINITY;
@@ -56,15 +88,21 @@ impl Position {
         // Setup aspiration window once we have a reliable evaluation from previous iterations.
         // At very shallow depths, the evaluation can be too unstable.
         if search_depth >= asp_window_d_min() {
-            alpha = (t.eval - delta).max(-Eval::INFINITY);
-            beta = (t.eval + delta).min(Eval::INFINITY);
+            alpha = t.eval.saturating_sub(delta);
+            beta = t.eval.saturating_add(delta);
         }
 
         loop {
             // Search within the window
             let v = self.pvsearch::<Root>(t, tt, tb, &mut pv, alpha, beta, search_depth, false);
 
+            // If we were stopped, the score is incomplete so skip the window logic. Every move in the
+            // PV has been fully searched and beat the previous best, so keep it if there is one.
             if t.stop {
+                if !pv.is_empty() {
+                    t.pv = pv;
+                }
+
                 return -Eval::INFINITY;
             }
 
@@ -73,10 +111,18 @@ impl Position {
             // Move beta towards alpha to narrow the window from above, and
             // expand alpha downward to catch the actual value.
             if v <= alpha {
+                if MAIN && !t.silent {
+                    self.print_info(t, tt, full_depth, v, " upperbound");
+                }
+
                 beta = Eval::midpoint(alpha, beta);
-                alpha = (v - delta).max(-Eval::INFINITY);
+                alpha = v.saturating_sub(delta);
                 search_depth = full_depth;
 
+                if MAIN && !t.silent && t.debug {
+                    t.out.println(format_args!("info string aspiration fail low, window {} {}", alpha.0, beta.0));
+                }
+
                 // Gradually expand the aspiration window for the next attempt.
                 delta += (delta as f32 * asp_window_expansion_fail_low()) as i32;
             }
@@ -85,9 +131,17 @@ impl Position {
             // Expand beta upward to catch the actual value, and save the PV.
             else if v >= beta {
                 alpha = (beta - delta).max(alpha);
-                beta = (v + delta).min(Eval::INFINITY);
+                beta = v.saturating_add(delta);
                 t.pv = pv.clone();
 
+                if MAIN && !t.silent {
+                    self.print_info(t, tt, full_depth, v, " lowerbound");
+
+                    if t.debug {
+                        t.out.println(format_args!("info string aspiration fail high, window {} {}", alpha.0, beta.0));
+                    }
+                }
+
                 // Depth reduction on fail-high.
                 // When we fail high, we often don't need full depth to prove the position is good.
                 if !v.is_terminal() {
@@ -105,3 +159,49 @@ impl Position {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time_management::timecontrol::TimeControl;
+
+    #[test]
+    fn test_seldepth_monotonic() {
+        let tt = TT::default();
+        let tb = SyzygyTB::default();
+        let mut pos: Position = "kiwipete".parse().unwrap();
+        let mut t = Thread::placeholder();
+        t.prepare_search(pos.board.state.halfmoves);
+
+        // Seldepth is only reset when a new search starts, so it should never drop between iterations.
+        let mut prev = 0;
+        for _ in 0..8 {
+            t.eval = pos.asp_window::<false>(&mut t, &tt, &tb);
+            t.depth += 1;
+
+            assert!(t.seldepth >= prev);
+            assert!(t.seldepth >= t.depth as usize);
+            prev = t.seldepth;
+        }
+    }
+
+    #[test]
+    fn test_soft_stop_not_counted() {
+        let tt = TT::default();
+        let tb = SyzygyTB::default();
+        let mut pos: Position = "startpos".parse().unwrap();
+        let mut t = Thread::placeholder();
+        t.prepare_search(pos.board.state.halfmoves);
+
+        assert!(pos.iterate::<false>(&mut t, &tt, &tb));
+        assert_eq!(t.depth, 1);
+
+        // The soft limit has already passed, so we stop after the first root move of the next iteration.
+        // That is well before the hard limit is first polled.
+        t.tm.set_tc(TimeControl::FixedTime(0), pos.stm());
+        assert!(!pos.iterate::<false>(&mut t, &tt, &tb));
+        assert!(t.stop);
+        assert_eq!(t.depth, 1);
+        assert!(pos.board.is_legal(t.best_move()));
+    }
+}
@@ -1,6 +1,6 @@
 pub mod iterative_deepening;
 
-mod pruning;
+pub mod pruning;
 mod pvsearch;
 mod qsearch;
 
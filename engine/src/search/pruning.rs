@@ -1,3 +1,5 @@
+use std::array;
+
 use chess::types::{Depth, board::Board, eval::Eval, moves::Move};
 
 #[allow(clippy::wildcard_imports)]
@@ -64,10 +66,18 @@ pub fn can_apply_fp(depth: Depth, r: i32, eval: Eval, alpha: Eval) -> bool {
     lmr_depth <= fp_d_min() && eval + fp_margin < alpha
 }
 
+/// Probcut.
+/// If a shallower search of a good capture beats beta by a margin, then a full search probably will too.
+/// Skip this if the TT already tells us, from at least as deep as the probcut search, that the position
+/// is not that good.
+pub fn can_apply_probcut(depth: Depth, is_pv: bool, in_check: bool, beta: Eval, tt_depth: Depth, tt_value: Eval, pc_beta: Eval) -> bool {
+    !is_pv && !in_check && !beta.is_terminal() && depth >= pc_d_min() && (tt_depth < depth - (pc_depth_red() - 1) || tt_value >= pc_beta)
+}
+
 /// Late move reductions.
 /// Reduce the search depth for moves with bad move ordering.
 pub fn can_apply_lmr(depth: Depth, moves_tried: usize, is_pv: bool) -> bool {
-    depth >= 2 && moves_tried >= lmr_m_min() + usize::from(is_pv)
+    depth >= 2 && moves_tried >= if is_pv { lmr_min_moves_pv() } else { lmr_min_moves_nonpv() }
 }
 
 pub const LMR_SCALE: i32 = 1024;
@@ -94,3 +104,60 @@ pub fn lmr_base_reduction(depth: Depth, moves_tried: usize) -> i32 {
         (lmr_base + f32::from(depth).ln() * (moves_tried as f32).ln() / lmr_mult) as i32 * LMR_SCALE
     }
 }
+
+/// Get the full late move reduction table, indexed by [depth][moves tried].
+/// This is only used for inspecting the reductions, so is not performance critical.
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+pub fn lmr_table() -> [[i32; 64]; 64] {
+    array::from_fn(|depth| array::from_fn(|moves_tried| lmr_base_reduction(depth as Depth, moves_tried) / LMR_SCALE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lmr_min_moves() {
+        for (is_pv, min_moves) in [(true, lmr_min_moves_pv()), (false, lmr_min_moves_nonpv())] {
+            assert!(!can_apply_lmr(8, min_moves - 1, is_pv));
+            assert!(can_apply_lmr(8, min_moves, is_pv));
+
+            // Never reduce at depth 1.
+            assert!(!can_apply_lmr(1, min_moves + 10, is_pv));
+        }
+    }
+
+    #[test]
+    fn test_probcut_tt_gate() {
+        let (beta, pc_beta) = (Eval(0), Eval(200));
+        let depth = pc_d_min() + 4;
+        let probcut = |tt_depth| can_apply_probcut(depth, false, false, beta, tt_depth, Eval::DRAW, pc_beta);
+
+        // A TT entry at least as deep as the probcut search that fails to reach pc_beta stops us.
+        assert!(!probcut(depth - pc_depth_red() + 1));
+        assert!(probcut(depth - pc_depth_red()));
+    }
+
+    #[test]
+    #[cfg(feature = "tune")]
+    fn test_probcut_d_min_tunable() {
+        use crate::tunables::params::tunables::set_tunable;
+
+        /// Restores the tunable when dropped, even if an assertion fails.
+        struct Restore(&'static str, String);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                set_tunable(self.0, &self.1).unwrap();
+            }
+        }
+
+        let (beta, pc_beta) = (Eval(0), Eval(200));
+        let probcut = |depth| can_apply_probcut(depth, false, false, beta, 0, Eval::DRAW, pc_beta);
+
+        let _restore = Restore("pc_d_min", pc_d_min().to_string());
+        assert!(probcut(6));
+        set_tunable("pc_d_min", "7").unwrap();
+        assert!(!probcut(6));
+        assert!(probcut(7));
+    }
+}
@@ -7,13 +7,13 @@ use chess::{
 };
 
 use crate::{
-    movepick::{MPStage, MovePicker, SearchType},
+    movepick::{MovePicker, SearchType},
     position::Position,
     search::{
         NodeType, OffPV,
         pruning::{
-            LMR_SCALE, can_apply_fp, can_apply_hp, can_apply_iir, can_apply_lmp, can_apply_lmr, can_apply_nmp, can_apply_razoring,
-            can_apply_rfp, lmr_base_reduction,
+            LMR_SCALE, can_apply_fp, can_apply_hp, can_apply_iir, can_apply_lmp, can_apply_lmr, can_apply_nmp, can_apply_probcut,
+            can_apply_razoring, can_apply_rfp, lmr_base_reduction,
         },
     },
     tb::probe::{SyzygyTB, TB_HITS, WDL},
@@ -23,9 +23,10 @@ use crate::{
         table::TT,
     },
     tunables::params::tunables::{
-        ext_d_min, ext_double, ext_mult, ext_triple, hist_noisy_div, hist_quiet_div, lmp_base, lmr_cutnode, lmr_evaldiff, lmr_givecheck,
-        lmr_histscale, lmr_incheck, lmr_nonimprov, lmr_nonpv, lmr_offset, lmr_ttdeeper, lmr_ttnoisy, lmr_ttpv, lmr_ver_e_min, nmp_base,
-        nmp_factor, pc_beta_base, pc_beta_non_improving, pc_lerp, rfp_lerp, sp_d_max, sp_noisy_margin, sp_quiet_margin,
+        ext_d_min, ext_double, ext_mult, ext_recap_d_max, ext_triple, hist_noisy_div, hist_quiet_div, lmp_base, lmr_cutnode, lmr_evaldiff,
+        lmr_givecheck, lmr_histscale, lmr_incheck, lmr_nonimprov, lmr_nonpv, lmr_offset, lmr_ttdeeper, lmr_ttnoisy, lmr_ttpv,
+        lmr_ver_e_min, nmp_base, nmp_factor, pc_beta_base, pc_beta_non_improving, pc_depth_red, pc_lerp, rfp_lerp, root_rep_penalty,
+        sp_d_max, sp_noisy_margin, sp_quiet_margin,
     },
 };
 
@@ -57,9 +58,18 @@ impl Position {
         mut depth: Depth,
         cutnode: bool,
     ) -> Eval {
+        let in_check = self.board.in_check();
+
+        // Check extension: never drop into qsearch while in check, and give forcing lines an extra ply.
+        // This happens before the clamp below, so it can never push the depth past MAX_PLY - 1.
+        // Double checks get this single extension too, rather than an extra one on the parent side.
+        if !NT::RT && in_check {
+            depth += 1;
+        }
+
         // Base case: depth = 0.
         if depth <= 0 {
-            return self.qsearch::<NT::Next>(t, tt, alpha, beta);
+            return self.qsearch::<NT::Next>(t, tt, alpha, beta, 0);
         }
 
         // Make sure we don't search too deep if extensions are going crazy.
@@ -76,16 +86,15 @@ impl Position {
             pv.clear();
 
             // Update seldepth.
-            // Seldepth counts from 1.
+            // Seldepth counts from 1, and is only reset when a new search starts.
             t.seldepth = t.seldepth.max(t.ply + 1);
         }
 
-        let in_check = self.board.in_check();
-
         if !NT::RT {
             // Check for upcoming draw.
-            if alpha < Eval::DRAW && self.board.upcoming_repetition(t.ply) {
-                alpha = Eval::dithered_draw(t.nodes as i32);
+            let draw = t.draw_score();
+            if alpha < draw && self.board.upcoming_repetition(t.ply) {
+                alpha = draw;
                 if alpha >= beta {
                     return alpha;
                 }
@@ -93,12 +102,12 @@ impl Position {
 
             // Check for immediate draw.
             if self.board.is_draw(t.ply_from_null) {
-                return Eval::dithered_draw(t.nodes as i32);
+                return draw;
             }
 
             // Check if we are searching too deep.
             if t.ply >= MAX_PLY {
-                return if in_check { Eval::dithered_draw(t.nodes as i32) } else { self.evaluate() };
+                return if in_check { t.draw_score() } else { self.evaluate() };
             }
 
             // Mate distance pruning.
@@ -161,7 +170,7 @@ impl Position {
 
             let (tb_bound, tb_value) = match wdl {
                 WDL::Win => (Bound::Lower, Eval::tb_mate_in(t.ply)),
-                WDL::Draw => (Bound::Exact, Eval::dithered_draw(t.nodes as i32)),
+                WDL::Draw => (Bound::Exact, t.draw_score()),
                 WDL::Loss => (Bound::Upper, Eval::tb_mated_in(t.ply)),
             };
 
@@ -199,9 +208,9 @@ impl Position {
         // Otherwise try to get eval from the tt if the position has been evaluated and the bound
         // is tighter. If we can't do that, then just evaluate the position from scratch.
         else if tt_depth > -TT_DEPTH_OFFSET {
-            raw_value = if tt_eval.is_valid() { tt_eval } else { self.evaluate() };
+            raw_value = self.raw_eval(tt_eval);
 
-            let mut e = self.adjust_eval(t, raw_value);
+            let mut e = self.corrected_eval(t, raw_value);
             t.ss_mut().eval = e;
 
             // If we have a TT hit with a tighter bound than our static eval, use the TT value.
@@ -214,7 +223,7 @@ impl Position {
         // We can't use anything else: evaluate position from scratch.
         else {
             raw_value = self.evaluate();
-            t.ss_mut().eval = self.adjust_eval(t, raw_value);
+            t.ss_mut().eval = self.corrected_eval(t, raw_value);
 
             // Throw the static eval into the tt if we won't overwrite anything.
             tt.insert(self.hash(), Bound::None, Move::NONE, raw_value, -Eval::INFINITY, TT_DEPTH_UNSEARCHED, t.ply, tt_pv);
@@ -239,7 +248,7 @@ impl Position {
 
             // Razoring.
             if can_apply_razoring(depth, eval, alpha) {
-                let v = self.qsearch::<OffPV>(t, tt, alpha, beta);
+                let v = self.qsearch::<OffPV>(t, tt, alpha, beta, 0);
                 // If the qsearch still can't catch up, cut this node.
                 if v <= alpha {
                     return v;
@@ -251,7 +260,7 @@ impl Position {
                 let r = (nmp_base() + depth / nmp_factor()).min(depth) + Depth::from(tt_move.flag().is_noisy());
 
                 self.make_null(t);
-                let v = -self.nwsearch(t, tt, tb, child_pv, -beta + Eval(1), depth - r, false);
+                let v = -self.nwsearch(t, tt, tb, child_pv, (-beta).saturating_add(1), depth - r, false);
                 self.undo_null(t);
 
                 // cutoff above beta.
@@ -269,11 +278,11 @@ impl Position {
         // -----------------------------------
         //              Probcut
         // -----------------------------------
-        let pc_beta = beta + pc_beta_base() + (i32::from(!improving) * pc_beta_non_improving());
+        let pc_beta = beta.saturating_add(pc_beta_base() + i32::from(!improving) * pc_beta_non_improving());
 
-        if !NT::PV && !in_check && !beta.is_terminal() && depth >= 5 && !(tt_depth >= depth - 3 && tt_value < pc_beta) {
+        if can_apply_probcut(depth, NT::PV, in_check, beta, tt_depth, tt_value, pc_beta) {
             let mut mp = MovePicker::new(SearchType::Pc, in_check, tt_move, pc_beta - t.ss().eval);
-            let pc_depth = depth - 4;
+            let pc_depth = depth - pc_depth_red();
 
             while let Some(m) = mp.next(&self.board, t) {
                 // Ignore excluded move.
@@ -284,11 +293,11 @@ impl Position {
                 self.make_move(m, t);
 
                 // Do a quick qsearch to see if the move is worth looking at.
-                let mut v = -self.qsearch::<OffPV>(t, tt, -pc_beta, -pc_beta + 1);
+                let mut v = -self.qsearch::<OffPV>(t, tt, -pc_beta, (-pc_beta).saturating_add(1), 0);
 
                 // If it is, then do the full search.
                 if v >= pc_beta {
-                    v = -self.nwsearch(t, tt, tb, pv, -pc_beta + 1, pc_depth, !cutnode);
+                    v = -self.nwsearch(t, tt, tb, pv, (-pc_beta).saturating_add(1), pc_depth, !cutnode);
                 }
 
                 self.undo_move(t);
@@ -322,8 +331,15 @@ impl Position {
         let lmp_margin = ((depth * depth + lmp_base()) / (2 - i16::from(improving))) as usize;
         let see_margins = [sp_noisy_margin() * i32::from(depth * depth), sp_quiet_margin() * i32::from(depth)];
 
-        let mut mp = MovePicker::new(SearchType::Pv, in_check, tt_move, Eval::DRAW);
-        while let Some(m) = mp.next(&self.board, t) {
+        let mut mp = if NT::RT {
+            // At the root, try the best move from the deepest completed iteration across all threads
+            // straight after our own TT move.
+            MovePicker::new(SearchType::Rt, in_check, tt_move, Eval::DRAW).with_shared(t.shared_best_move())
+        } else {
+            MovePicker::new(SearchType::Pv, in_check, tt_move, Eval::DRAW)
+        };
+
+        while let Some((m, hist_score)) = mp.next_scored(&self.board, t) {
             debug_assert!(!m.is_none());
 
             // Ignore excluded move.
@@ -335,7 +351,6 @@ impl Position {
 
             let start_nodes = t.nodes;
             let is_quiet = m.flag().is_quiet();
-            let hist_score = t.hist_score(&self.board, m);
             let mut new_depth = depth - 1;
 
             // Late move reductions.
@@ -368,7 +383,7 @@ impl Position {
             // If all captures happen on this move and we lose, prune this move.
             if depth <= sp_d_max()
                 && !best_value.is_terminal()
-                && mp.stage > MPStage::PvNoisyWin
+                && mp.past_good_noisies()
                 && !self.board.see(m, Eval(-see_margins[usize::from(is_quiet)]))
             {
                 continue;
@@ -388,7 +403,7 @@ impl Position {
                 && tt_bound.has(Bound::Lower)
                 && tt_depth >= depth - 3
             {
-                let ext_beta = (tt_value - i32::from(depth * ext_mult())).max(-Eval::INFINITY);
+                let ext_beta = tt_value.saturating_sub(i32::from(depth * ext_mult()));
 
                 // Search all moves except the TT move at reduced depth.
                 t.ss_mut().excluded = Some(tt_move);
@@ -423,6 +438,19 @@ impl Position {
                 new_depth += ext;
             }
 
+            // Recapture extension: taking back on the square of the last capture is usually forced, so
+            // don't let it eat into the depth of the line.
+            let prev = self.board.state.mov;
+            if !NT::PV
+                && depth <= ext_recap_d_max()
+                && m.flag().is_cap()
+                && !prev.is_none()
+                && prev.flag().is_cap()
+                && m.dst() == prev.dst()
+            {
+                new_depth += 1;
+            }
+
             // -----------------------------------
             //             Make Move
             // -----------------------------------
@@ -430,6 +458,7 @@ impl Position {
             tt.prefetch(self.hash());
 
             let gives_check = self.board.in_check();
+            let repeats = NT::RT && self.board.is_repetition(t.ply_from_null);
             let mut v = -Eval::INFINITY;
 
             // Late move reductions.
@@ -489,6 +518,12 @@ impl Position {
             }
 
             if NT::RT {
+                // If we think we are better, prefer playing on over repeating the position.
+                // The penalty is small, so a repetition is still preferred over a losing move.
+                if repeats && !v.is_terminal() && t.avg_eval > Eval(root_rep_penalty()) {
+                    v -= root_rep_penalty();
+                }
+
                 t.tm.update_nodes(m, t.nodes - start_nodes);
                 t.avg_eval = if t.avg_eval.is_valid() { Eval::midpoint(t.avg_eval, v) } else { v };
             }
@@ -514,12 +549,21 @@ impl Position {
             }
 
             // Add move to history.
-            if is_quiet {
-                // SAFETY: at most MAX_MOVES legal moves can be tried in this node.
-                unsafe { quiets_tried.push_unchecked(m) };
-            } else if m.flag().is_cap() {
-                // SAFETY: at most MAX_MOVES legal moves can be tried in this node.
-                unsafe { caps_tried.push_unchecked(m) };
+            // At most MAX_MOVES legal moves can be tried in this node, so these can't fill up.
+            // If they somehow did, we would only lose the history update for the extra moves.
+            let tried = if is_quiet { &mut quiets_tried } else { &mut caps_tried };
+            if is_quiet || m.flag().is_cap() {
+                debug_assert!(!tried.is_full());
+                let _ = tried.try_push(m);
+            }
+
+            // Stop between root moves once we have used the optimal time, rather than finishing
+            // the iteration. This happens after the move has been added to the PV, so the moves
+            // searched so far still count. We only do this once an iteration has completed.
+            if NT::RT && t.depth > 0 && !t.deterministic && t.tm.soft_expired(t.nodes, t.best_move()) {
+                t.tm.raise_stop();
+                t.stop = true;
+                break;
             }
         }
 
@@ -538,6 +582,11 @@ impl Position {
             best_value = best_value.clamp(tb_min, tb_max);
         }
 
+        // We stopped between root moves, so this is not a real bound to store.
+        if t.stop {
+            return best_value;
+        }
+
         let bound = if best_value >= beta {
             // Insert this position in at a lower bound.
             // We stopped searching after the beta cutoff, as we proved the position is so strong
@@ -551,6 +600,12 @@ impl Position {
         } else if !NT::PV || best_move.is_none() {
             // Insert this position in at an upper bound.
             // If we never updated the best move, then none of the moves were better than alpha - so at best, the position is equal to alpha.
+
+            // If we searched a quiet TT move first and it was not good enough, trust it a bit less next time.
+            if best_move.is_none() && !tt_move.is_none() && quiets_tried.first() == Some(&tt_move) {
+                t.update_tt_fail_low(tt_move, depth, &self.board);
+            }
+
             Bound::Upper
         } else {
             // We have searched all the moves and have an exact bound for the score.
@@ -559,7 +614,8 @@ impl Position {
         };
 
         // Update correction history.
-        if !best_move.flag().is_cap() && !in_check && bound.is_usable(best_value, t.ss().eval) {
+        // Terminal scores say nothing about how good the static eval was, so skip them.
+        if !best_move.flag().is_cap() && !in_check && !best_value.is_terminal() && bound.is_usable(best_value, t.ss().eval) {
             t.update_corrhist(&self.board, best_value, depth);
         }
 
@@ -12,18 +12,21 @@ use crate::{
         entry::{Bound, TT_DEPTH_OFFSET, TT_DEPTH_QS, TT_DEPTH_UNSEARCHED},
         table::TT,
     },
-    tunables::params::tunables::{fp_qs_base, qs_conservative_beta_lerp, qs_stand_pat_beta_lerp, sp_qs_margin},
+    tunables::params::tunables::{fp_qs_base, fp_qs_see, qs_conservative_beta_lerp, qs_max_ply, qs_stand_pat_beta_lerp, sp_qs_margin},
 };
 
 impl Position {
     /// Quiescence search.
     /// We use this to avoid the "horizon" effect, by continuing the search
     /// until all captures have been made.
+    /// `qs_ply` is the number of plies since we dropped into qsearch.
     #[allow(clippy::too_many_arguments, clippy::too_many_lines, clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
-    pub fn qsearch<NT: NodeType>(&mut self, t: &mut Thread, tt: &TT, mut alpha: Eval, beta: Eval) -> Eval {
+    pub fn qsearch<NT: NodeType>(&mut self, t: &mut Thread, tt: &TT, mut alpha: Eval, beta: Eval, qs_ply: usize) -> Eval {
         // Check for upcoming repetition.
-        if alpha < Eval::DRAW && self.board.upcoming_repetition(t.ply) {
-            alpha = Eval::dithered_draw(t.nodes as i32);
+        // A cycle needs at least 3 reversible plies, which cannot happen straight after a null move.
+        let draw = t.draw_score();
+        if t.ply_from_null >= 3 && alpha < draw && self.board.upcoming_repetition(t.ply) {
+            alpha = draw;
             if alpha >= beta {
                 return alpha;
             }
@@ -41,13 +44,13 @@ impl Position {
                 Eval::DRAW
             } else {
                 let raw = self.evaluate();
-                self.adjust_eval(t, raw)
+                self.corrected_eval(t, raw)
             };
         }
 
         // Stop searching if position is ruled as a draw.
         if self.board.is_draw(t.ply_from_null) {
-            return Eval::DRAW;
+            return draw;
         }
 
         // -----------------------------------
@@ -94,10 +97,10 @@ impl Position {
             futility = -Eval::INFINITY;
         } else {
             // Stand pat evaluation: assume we can choose not to make any move.
-            raw_value = if tt_eval.is_valid() { tt_eval } else { self.evaluate() };
+            raw_value = self.raw_eval(tt_eval);
 
             // Adjust evaluation with correction history.
-            best_value = self.adjust_eval(t, raw_value);
+            best_value = self.corrected_eval(t, raw_value);
 
             t.ss_mut().eval = best_value;
 
@@ -130,6 +133,11 @@ impl Position {
 
             // Raise alpha if our stand pat evaluation is better.
             alpha = alpha.max(best_value);
+
+            // Stand pat if we have gone too deep, so long capture sequences can't explode the search.
+            if qs_ply >= qs_max_ply() {
+                return best_value;
+            }
         }
 
         // -----------------------------------
@@ -149,7 +157,7 @@ impl Position {
                 // Futility pruning in qsearch.
                 // If our position + bonus can't reach alpha, and the move doesn't
                 // win material according to SEE, skip it.
-                if futility <= alpha && !self.board.gives_check(m) && !self.board.see(m, Eval(1)) {
+                if futility <= alpha && !self.board.gives_check(m) && !self.board.see(m, Eval(fp_qs_see())) {
                     best_value = best_value.max(futility);
                     continue;
                 }
@@ -166,7 +174,7 @@ impl Position {
             //             Make Move
             // -----------------------------------
             self.make_move(m, t);
-            let v = -self.qsearch::<NT::Next>(t, tt, -beta, -alpha);
+            let v = -self.qsearch::<NT::Next>(t, tt, -beta, -alpha, qs_ply + 1);
             self.undo_move(t);
 
             if t.stop {
@@ -218,3 +226,23 @@ impl Position {
         best_value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::OnPV;
+
+    #[test]
+    fn test_qsearch_knight_promo_fork() {
+        let tt = TT::default();
+        let mut pos: Position = "fen 8/2q1P1k1/8/8/8/8/PP6/7K w - - 0 1".parse().unwrap();
+        let mut t = Thread::placeholder();
+        t.prepare_search(pos.board.state.halfmoves);
+
+        // e8=Q also wins material, but e8=N+ forks the king and queen and nets the most,
+        // so it should be the best move stored in the TT.
+        let v = pos.qsearch::<OnPV>(&mut t, &tt, -Eval::INFINITY, Eval::INFINITY, 0);
+        assert!(v > Eval(500));
+        assert_eq!(tt.probe(pos.hash()).unwrap().mov(), pos.board.find_move("e7e8n").unwrap());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod output;
 pub mod pv;
 pub mod stack;
 pub mod thread;
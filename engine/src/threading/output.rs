@@ -0,0 +1,25 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// Where the info and bestmove lines of a search are written.
+#[derive(Clone, Debug, Default)]
+pub enum SearchOutput {
+    /// Print to stdout, as UCI expects.
+    #[default]
+    Stdout,
+
+    /// Collect the lines, so they can be inspected after the search.
+    Buffer(Arc<Mutex<Vec<String>>>),
+}
+
+impl SearchOutput {
+    /// Write a single line.
+    pub fn println(&self, args: fmt::Arguments) {
+        match self {
+            Self::Stdout => println!("{args}"),
+            Self::Buffer(lines) => lines.lock().unwrap().push(args.to_string()),
+        }
+    }
+}
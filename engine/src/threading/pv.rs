@@ -2,7 +2,7 @@ use std::fmt::Write;
 
 use chess::{
     defs::MAX_PLY,
-    types::{castling::CastlingMask, moves::Move},
+    types::{board::Board, moves::Move},
 };
 
 /// This allows us to keep track of the current PV.
@@ -33,14 +33,90 @@ impl PVLine {
         self.length = 0;
     }
 
+    /// Whether the PV line has no moves.
+    pub const fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// The longest part of the PV that can be played from the given board.
+    /// This stops at the first move that is not legal in the position it would be played in.
+    pub fn legal_prefix(&self, b: &Board) -> &[Move] {
+        let mut b = b.clone();
+        let mut len = 0;
+
+        for &m in &self.moves[0..self.length] {
+            if !b.is_legal(m) {
+                break;
+            }
+
+            b.make_move(m);
+            len += 1;
+        }
+
+        &self.moves[0..len]
+    }
+
     /// Print out the PV according to UCI format.
-    pub fn to_uci(&self, cm: &CastlingMask) -> String {
+    /// The PV is replayed from the given board so each move is formatted in its own position.
+    /// Only the legal part of the PV is printed.
+    pub fn to_uci(&self, b: &Board) -> String {
         let mut s = String::from("pv");
+        let moves = self.legal_prefix(b);
+        let mut b = b.clone();
 
-        for m in &self.moves[0..self.length] {
-            let _ = write!(s, " {}", m.to_uci(cm));
+        for &m in moves {
+            let _ = write!(s, " {}", m.to_uci(&b.castlingmask));
+            b.make_move(m);
         }
 
         s
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pv_frc_castling() {
+        let mut b: Board = "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1".parse().unwrap();
+        let root = b.clone();
+
+        let mut pv = PVLine::default();
+        for (i, uci) in ["a2a3", "e8b8", "e1g1", "a7a6"].iter().enumerate() {
+            let m = b.find_move(uci).unwrap();
+            pv.moves[i] = m;
+            pv.length += 1;
+            b.make_move(m);
+        }
+
+        assert_eq!(pv.to_uci(&root), "pv a2a3 e8b8 e1g1 a7a6");
+    }
+
+    #[test]
+    fn test_pv_legal_prefix() {
+        let root: Board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+        let mut b = root.clone();
+
+        let mut pv = PVLine::default();
+        for (i, uci) in ["e2e4", "e7e5", "g1f3"].iter().enumerate() {
+            let m = b.find_move(uci).unwrap();
+            pv.moves[i] = m;
+            pv.length += 1;
+            b.make_move(m);
+        }
+
+        // The same move again is not legal: the knight has already left g1.
+        pv.moves[3] = pv.moves[2];
+        pv.moves[4] = root.find_move("d2d4").unwrap();
+        pv.length = 5;
+
+        assert_eq!(pv.legal_prefix(&root), &pv.moves[0..3]);
+        assert_eq!(pv.to_uci(&root), "pv e2e4 e7e5 g1f3");
+
+        // A null move ends the line as well.
+        pv.moves[1] = Move::NONE;
+        assert_eq!(pv.legal_prefix(&root), &pv.moves[0..1]);
+        assert_eq!(pv.to_uci(&root), "pv e2e4");
+    }
+}
@@ -2,7 +2,7 @@ use std::{
     array,
     sync::{
         Arc,
-        atomic::{AtomicBool, AtomicU64},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
     },
 };
 
@@ -14,15 +14,16 @@ use chess::{
 
 use crate::{
     history::{
-        conthist::{CONT_NUM, ContHist, PieceTo},
+        capconthist::CapContHist,
+        conthist::{CONT_NUM, CONT_PLIES, ContHist, PieceTo, ch_scale},
         corrhist::{CorrHist, correction_bonus},
         hist_delta,
         noisyhist::NoisyHist,
         quiethist::QuietHist,
     },
-    threading::{pv::PVLine, stack::SearchStackEntry},
+    threading::{output::SearchOutput, pv::PVLine, stack::SearchStackEntry},
     time_management::{timecontrol::TimeControl, timemanager::TimeManager},
-    tunables::params::tunables::{hist_corr_other, hist_corr_pawn},
+    tunables::params::tunables::{cch_scale, hist_corr_max, hist_corr_other, hist_corr_pawn, hist_tt_fail_low},
 };
 
 #[derive(Clone, Debug)]
@@ -30,6 +31,14 @@ pub struct Thread {
     // Time management.
     pub tm: TimeManager,
     pub stop: bool,
+    pub deterministic: bool,
+    pub debug: bool,
+    pub silent: bool,
+    pub out: SearchOutput,
+    pub contempt: i32,
+
+    // Best root move shared between threads, packed as (depth << 16 | move).
+    pub shared_best: Arc<AtomicU32>,
 
     // Search data.
     pub ply: usize,
@@ -46,6 +55,7 @@ pub struct Thread {
     pub hist_quiet: QuietHist,
     pub hist_noisy: NoisyHist,
     pub hist_conts: [ContHist; CONT_NUM],
+    pub hist_capcont: CapContHist,
     pub hist_corr_pawn: CorrHist,
     pub hist_corr_major_w: CorrHist,
     pub hist_corr_major_b: CorrHist,
@@ -57,6 +67,13 @@ impl Thread {
         Self {
             tm,
             stop: false,
+            deterministic: false,
+            debug: false,
+            silent: false,
+            out: SearchOutput::default(),
+            contempt: 0,
+
+            shared_best: Arc::new(AtomicU32::new(0)),
 
             ply: 0,
             depth: 0,
@@ -71,6 +88,7 @@ impl Thread {
             hist_quiet: QuietHist::default(),
             hist_noisy: NoisyHist::default(),
             hist_conts: array::from_fn(|_| ContHist::default()),
+            hist_capcont: CapContHist::default(),
 
             hist_corr_pawn: CorrHist::default(),
             hist_corr_major_w: CorrHist::default(),
@@ -100,15 +118,42 @@ impl Thread {
     }
 
     /// Whether we should stop searching.
+    /// The hard bound is polled from the first node. If that cuts the first iteration short,
+    /// iterative deepening still has a legal move to play.
     pub fn should_stop(&mut self) -> bool {
         self.stop || !self.tm.should_continue(self.nodes)
     }
 
+    /// The score to use for a drawn position.
+    /// Contempt makes draws worse for the side to move at the root, and better for the opponent.
+    /// This is dithered slightly unless the search should be deterministic.
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn draw_score(&self) -> Eval {
+        let draw = if self.deterministic { Eval::DRAW } else { Eval::dithered_draw(self.nodes as i32) };
+        let contempt = if self.ply.is_multiple_of(2) { -self.contempt } else { self.contempt };
+        Eval(draw.0 + contempt)
+    }
+
     /// The best move found by this thread.
     pub const fn best_move(&self) -> Move {
         self.pv.moves[0]
     }
 
+    /// Share the best move from the iteration we just completed with the other threads.
+    /// This only replaces the shared move if we have searched at least as deep.
+    #[allow(clippy::cast_sign_loss)]
+    pub fn share_best_move(&self) {
+        let packed = (u32::from(self.depth as u16) << 16) | u32::from(self.best_move().0);
+        self.shared_best.fetch_max(packed, Ordering::Relaxed);
+    }
+
+    /// The best root move shared by the thread that has searched the deepest.
+    /// This may be from a different position if another thread is still finishing a previous search.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn shared_best_move(&self) -> Move {
+        Move(self.shared_best.load(Ordering::Relaxed) as u16)
+    }
+
     /// Prepare this thread to search.
     pub fn prepare_search(&mut self, halfmoves: usize) {
         self.tm.prepare_search();
@@ -148,14 +193,10 @@ impl Thread {
     }
 
     /// Whether the current position is improving.
+    /// Compares against our most recent static eval, skipping any plies where we were in check
+    /// (these have an eval of -INFINITY). If we have no previous static eval, assume we are improving.
     pub fn is_improving(&self) -> bool {
-        if self.ply >= 2 && self.ss_at(2).eval != -Eval::INFINITY {
-            self.ss().eval > self.ss_at(2).eval
-        } else if self.ply >= 4 && self.ss_at(4).eval != -Eval::INFINITY {
-            self.ss().eval > self.ss_at(4).eval
-        } else {
-            true
-        }
+        (2..=self.ply).step_by(2).map(|offset| self.ss_at(offset).eval).find(|&e| e != -Eval::INFINITY).is_none_or(|e| self.ss().eval > e)
     }
 
     /// Whether our opponent's position is getting worse.
@@ -178,8 +219,8 @@ impl Thread {
     pub fn get_prev_piecetos(&self) -> [Option<PieceTo>; CONT_NUM] {
         let mut pms = [None; CONT_NUM];
 
-        for (i, pm) in pms.iter_mut().enumerate() {
-            *pm = self.pieceto_at(i + 1);
+        for (pm, &offset) in pms.iter_mut().zip(CONT_PLIES.iter()) {
+            *pm = self.pieceto_at(offset);
         }
 
         pms
@@ -190,27 +231,74 @@ impl Thread {
         let (bonus, malus) = hist_delta(depth);
         self.hist_noisy.update(board, best, captures, bonus, malus);
 
+        if let Some(pt) = self.pieceto_at(1) {
+            self.hist_capcont.update(board, best, pt, captures, bonus, malus);
+        }
+
         if best.flag().is_quiet() {
             self.ss_mut().killer = Some(best);
             self.hist_quiet.update(board.stm, best, quiets, bonus, malus);
 
-            for i in 0..CONT_NUM {
-                if let Some(pt) = self.pieceto_at(i + 1) {
+            for (i, &offset) in CONT_PLIES.iter().enumerate() {
+                if let Some(pt) = self.pieceto_at(offset) {
                     self.hist_conts[i].update(best, pt, quiets, bonus, malus);
                 }
             }
         }
     }
 
+    /// Apply a malus to a quiet TT move that was searched first but failed to raise alpha.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn update_tt_fail_low(&mut self, tt_move: Move, depth: Depth, board: &Board) {
+        let (_, malus) = hist_delta(depth);
+        let malus = (i32::from(malus) * hist_tt_fail_low() / 1024) as i16;
+
+        self.hist_quiet.add_bonus(board.stm, tt_move, -malus);
+
+        for (i, &offset) in CONT_PLIES.iter().enumerate() {
+            if let Some(pt) = self.pieceto_at(offset) {
+                self.hist_conts[i].add_bonus(tt_move, pt, -malus);
+            }
+        }
+    }
+
+    /// Clear all the histories, including correction histories.
+    pub fn reset_histories(&mut self) {
+        self.hist_quiet = QuietHist::default();
+        self.hist_noisy = NoisyHist::default();
+        self.hist_conts = array::from_fn(|_| ContHist::default());
+        self.hist_capcont = CapContHist::default();
+
+        self.hist_corr_pawn = CorrHist::default();
+        self.hist_corr_major_w = CorrHist::default();
+        self.hist_corr_major_b = CorrHist::default();
+    }
+
+    /// Copy all histories from another thread.
+    pub fn copy_histories(&mut self, other: &Self) {
+        self.hist_quiet.clone_from(&other.hist_quiet);
+        self.hist_noisy.clone_from(&other.hist_noisy);
+        self.hist_conts.clone_from(&other.hist_conts);
+        self.hist_capcont.clone_from(&other.hist_capcont);
+
+        self.hist_corr_pawn.clone_from(&other.hist_corr_pawn);
+        self.hist_corr_major_w.clone_from(&other.hist_corr_major_w);
+        self.hist_corr_major_b.clone_from(&other.hist_corr_major_b);
+    }
+
     /// Get the history score for a given move.
     pub fn hist_score(&self, b: &Board, m: Move) -> i32 {
         if m.flag().is_cap() {
-            self.hist_noisy.get_bonus(b, m)
+            let mut v = self.hist_noisy.get_bonus(b, m);
+            if let Some(pt) = self.pieceto_at(1) {
+                v += self.hist_capcont.get_bonus(b, m, pt) * cch_scale() / 1024;
+            }
+            v
         } else {
             let mut v = self.hist_quiet.get_bonus(b.stm, m);
-            for i in 0..CONT_NUM {
-                if let Some(pt) = self.pieceto_at(i + 1) {
-                    v += self.hist_conts[i].get_bonus(m, pt);
+            for (i, &offset) in CONT_PLIES.iter().enumerate() {
+                if let Some(pt) = self.pieceto_at(offset) {
+                    v += self.hist_conts[i].get_bonus(m, pt) * ch_scale(i) / 1024;
                 }
             }
             v
@@ -219,14 +307,15 @@ impl Thread {
 
     #[rustfmt::skip]
     /// Get the correction score for a given board position according to our correction history.
+    /// This is capped so that a few noisy entries cannot swing the eval too far.
     pub fn correction_score(&self, b: &Board) -> Eval {
         let key = b.state.hash;
 
-        Eval (
-            hist_corr_pawn()  * self.hist_corr_pawn.get_bonus(key.pawn_key, b.stm)                            / 1024 +
-            hist_corr_other() * self.hist_corr_major_w.get_bonus(key.non_pawn_key[Color::White.idx()], b.stm) / 1024 +
-            hist_corr_other() * self.hist_corr_major_b.get_bonus(key.non_pawn_key[Color::Black.idx()], b.stm) / 1024
-        )
+        let v = hist_corr_pawn()  * self.hist_corr_pawn.get_bonus(key.pawn_key, b.stm)                            / 1024 +
+                hist_corr_other() * self.hist_corr_major_w.get_bonus(key.non_pawn_key[Color::White.idx()], b.stm) / 1024 +
+                hist_corr_other() * self.hist_corr_major_b.get_bonus(key.non_pawn_key[Color::Black.idx()], b.stm) / 1024;
+
+        Eval(v.clamp(-hist_corr_max(), hist_corr_max()))
     }
 
     /// Update the correction history.
@@ -239,3 +328,199 @@ impl Thread {
         self.hist_corr_major_b.add_bonus(key.non_pawn_key[Color::Black.idx()], b.stm, bonus);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_improving_skips_in_check() {
+        let mut t = Thread::placeholder();
+
+        // Our evals at ply 0, 2 (in check) and 4, with opponent evals in between.
+        t.stack[0].eval = Eval(100);
+        t.stack[1].eval = Eval(-100);
+        t.stack[2].eval = -Eval::INFINITY;
+        t.stack[3].eval = Eval(-100);
+        t.stack[4].eval = Eval(50);
+        t.ply = 4;
+        assert!(!t.is_improving());
+
+        t.stack[4].eval = Eval(150);
+        assert!(t.is_improving());
+
+        // Look further back than 4 plies if needed.
+        t.stack[0].eval = Eval(200);
+        t.stack[2].eval = -Eval::INFINITY;
+        t.stack[4].eval = -Eval::INFINITY;
+        t.stack[6].eval = Eval(150);
+        t.ply = 6;
+        assert!(!t.is_improving());
+
+        // No previous eval: assume improving.
+        t.stack[0].eval = -Eval::INFINITY;
+        assert!(t.is_improving());
+    }
+
+    #[test]
+    fn test_draw_score_contempt() {
+        let mut t = Thread::placeholder();
+        t.deterministic = true;
+        assert_eq!(t.draw_score(), Eval::DRAW);
+
+        // Draws are bad for the side to move at the root, and good for the opponent.
+        t.contempt = 20;
+        assert_eq!(t.draw_score(), Eval(-20));
+        t.ply = 1;
+        assert_eq!(t.draw_score(), Eval(20));
+
+        // Negative contempt seeks draws instead.
+        t.contempt = -20;
+        assert_eq!(t.draw_score(), Eval(-20));
+        t.ply = 2;
+        assert_eq!(t.draw_score(), Eval(20));
+    }
+
+    #[test]
+    fn test_hard_bound_polled() {
+        let tc: TimeControl = "wtime 1 btime 1".parse().unwrap();
+
+        // The clock is checked from the first node, even before depth 1 has finished.
+        let mut t = Thread::from_tc(tc, Color::White);
+        t.nodes = 10_000;
+        assert!(t.should_stop());
+
+        // Deterministic searches still respect the hard bound, so they can't lose on time.
+        let mut t = Thread::from_tc(tc, Color::White);
+        t.deterministic = true;
+        t.nodes = 10_000;
+        assert!(t.should_stop());
+
+        // An explicit stop is still respected.
+        let mut t = Thread::placeholder();
+        t.tm.raise_stop();
+        t.nodes = 10_000;
+        assert!(t.should_stop());
+    }
+
+    #[test]
+    fn test_reset_histories() {
+        let mut t = Thread::placeholder();
+        let b = Board::default();
+        let m = b.find_move("e2e4").unwrap();
+
+        t.ply = 1;
+        t.ss_at_mut(1).pieceto = Some(PieceTo::from(&b, b.find_move("g1f3").unwrap()));
+        t.update_history(m, 8, &b, &MoveList::new(), &MoveList::new());
+        t.ss_mut().eval = Eval::DRAW;
+        t.update_corrhist(&b, Eval(500), 8);
+        assert_ne!(t.hist_score(&b, m), 0);
+        assert_ne!(t.correction_score(&b), Eval::DRAW);
+
+        t.reset_histories();
+        assert_eq!(t.hist_score(&b, m), 0);
+        assert_eq!(t.correction_score(&b), Eval::DRAW);
+    }
+
+    #[test]
+    #[cfg(feature = "tune")]
+    fn test_tt_fail_low_malus() {
+        use crate::tunables::params::tunables::set_tunable;
+
+        /// Restores the tunable when dropped, even if an assertion fails.
+        struct Restore(&'static str, String);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                set_tunable(self.0, &self.1).unwrap();
+            }
+        }
+
+        let mut t = Thread::placeholder();
+        let b = Board::default();
+        let m = b.find_move("e2e4").unwrap();
+        let pt = PieceTo::from(&b, b.find_move("g1f3").unwrap());
+
+        t.ply = 1;
+        t.ss_at_mut(1).pieceto = Some(pt);
+
+        // Both the quiet and continuation histories of the TT move drop.
+        let _restore = Restore("hist_tt_fail_low", hist_tt_fail_low().to_string());
+        set_tunable("hist_tt_fail_low", "1024").unwrap();
+        t.update_tt_fail_low(m, 8, &b);
+        assert!(t.hist_quiet.get_bonus(b.stm, m) < 0);
+        assert!(t.hist_conts[0].get_bonus(m, pt) < 0);
+    }
+
+    #[test]
+    fn test_correction_is_bounded() {
+        let mut t = Thread::placeholder();
+        let b = Board::default();
+
+        t.ss_mut().eval = Eval::DRAW;
+        for _ in 0..100 {
+            t.update_corrhist(&b, Eval(2000), 30);
+        }
+        assert_eq!(t.correction_score(&b), Eval(hist_corr_max()));
+
+        for _ in 0..100 {
+            t.update_corrhist(&b, Eval(-2000), 30);
+        }
+        assert_eq!(t.correction_score(&b), Eval(-hist_corr_max()));
+    }
+
+    #[test]
+    fn test_prev_piecetos_skip_shallow_plies() {
+        let mut t = Thread::placeholder();
+        let b = Board::default();
+        let pt = PieceTo::from(&b, b.find_move("g1f3").unwrap());
+
+        for i in 0..=CONT_PLIES[CONT_NUM - 1] {
+            t.stack[i].pieceto = Some(pt);
+        }
+
+        // Only plies that exist in the search can be used.
+        t.ply = 1;
+        let pts = t.get_prev_piecetos();
+        assert!(pts[0].is_some());
+        assert!(pts[1..].iter().all(Option::is_none));
+
+        t.ply = CONT_PLIES[CONT_NUM - 1];
+        assert!(t.get_prev_piecetos().iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn test_share_best_move_keeps_deepest() {
+        let b = Board::default();
+        let e2e4 = b.find_move("e2e4").unwrap();
+        let d2d4 = b.find_move("d2d4").unwrap();
+
+        let mut deep = Thread::placeholder();
+        let mut shallow = Thread::placeholder();
+        shallow.shared_best = deep.shared_best.clone();
+        assert!(deep.shared_best_move().is_none());
+
+        deep.depth = 6;
+        deep.pv.update(e2e4, &PVLine::default());
+        deep.share_best_move();
+
+        shallow.depth = 4;
+        shallow.pv.update(d2d4, &PVLine::default());
+        shallow.share_best_move();
+        assert_eq!(shallow.shared_best_move(), e2e4);
+
+        shallow.depth = 7;
+        shallow.share_best_move();
+        assert_eq!(deep.shared_best_move(), d2d4);
+    }
+
+    #[test]
+    #[cfg(not(feature = "tune"))]
+    fn test_tt_fail_low_malus_off_by_default() {
+        let b: Board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+        let mut t = Thread::placeholder();
+        let e2e4 = b.find_move("e2e4").unwrap();
+
+        t.update_tt_fail_low(e2e4, 8, &b);
+        assert_eq!(t.hist_quiet.get_bonus(b.stm, e2e4), 0);
+    }
+}
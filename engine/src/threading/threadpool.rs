@@ -3,14 +3,17 @@ use std::{
     iter,
     sync::{
         Arc,
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
     },
     thread,
 };
 
-use chess::types::moves::Move;
+use chess::{
+    helpers::draw::GameResult,
+    types::{eval::Eval, moves::Move},
+};
 
-use super::thread::Thread;
+use super::{output::SearchOutput, thread::Thread};
 use crate::{
     position::Position,
     tb::probe::{SyzygyTB, TB_HITS, WDL},
@@ -24,24 +27,55 @@ pub struct ThreadPool {
     pub workers: Vec<Thread>,
     pub global_stop: Arc<AtomicBool>,
     pub global_nodes: Arc<AtomicU64>,
+    pub shared_best: Arc<AtomicU32>,
+    pub deterministic: bool,
+    pub debug: bool,
+    pub silent: bool,
+    pub out: SearchOutput,
+    pub contempt: i32,
+    pub game_result: Option<GameResult>,
+    pub game_searches: usize,
 }
 
 impl ThreadPool {
     /// Initialize a threadpool.
     pub fn new(global_stop: Arc<AtomicBool>) -> Self {
         let global_nodes = Arc::new(AtomicU64::new(0));
-        Self { main: Thread::idle(global_stop.clone(), global_nodes.clone()), workers: Vec::new(), global_stop, global_nodes }
+        Self {
+            main: Thread::idle(global_stop.clone(), global_nodes.clone()),
+            workers: Vec::new(),
+            global_stop,
+            global_nodes,
+            shared_best: Arc::new(AtomicU32::new(0)),
+            deterministic: false,
+            debug: false,
+            silent: false,
+            out: SearchOutput::default(),
+            contempt: 0,
+            game_result: None,
+            game_searches: 0,
+        }
     }
 
     /// Resize the threadpool to `n` workers.
+    /// New workers start with a copy of the main thread's histories.
     pub fn resize(&mut self, new_len: usize) {
-        self.main = Thread::idle(self.global_stop.clone(), self.global_nodes.clone());
-        self.workers.resize_with(new_len, || Thread::idle(self.global_stop.clone(), self.global_nodes.clone()));
+        self.workers.truncate(new_len);
+        self.workers.resize_with(new_len, || {
+            let mut t = Thread::idle(self.global_stop.clone(), self.global_nodes.clone());
+            t.copy_histories(&self.main);
+            t
+        });
     }
 
-    /// Reset all threads in the threadpool.
+    /// Reset all threads in the threadpool, ready for a new game.
     pub fn reset(&mut self) {
-        self.resize(self.workers.len());
+        for t in iter::once(&mut self.main).chain(self.workers.iter_mut()) {
+            t.reset_histories();
+            t.avg_eval = -Eval::INFINITY;
+        }
+
+        self.game_searches = 0;
     }
 }
 
@@ -49,6 +83,15 @@ impl ThreadPool {
 impl ThreadPool {
     /// Starts searching the given position.
     pub fn go(&mut self, pos: &mut Position, tc: TimeControl, tt: &TT, tb: &SyzygyTB) -> Move {
+        // There is nothing to search if we have no legal moves.
+        self.game_result = pos.board.game_result();
+        if let Some(res @ (GameResult::Checkmate | GameResult::Stalemate)) = self.game_result {
+            if !self.silent {
+                self.out.println(format_args!("info string {res}"));
+            }
+            return Move::NONE;
+        }
+
         // Check tablebase before searching anything.
         if let Some(res) = tb.probe_root(&pos.board) {
             let eval_wdl = match res.wdl {
@@ -57,12 +100,14 @@ impl ThreadPool {
                 WDL::Loss => "cp -20000 wdl 0 0 1000",
             };
 
-            println!(
-                "info depth 0 seldepth 0 score {} hashfull 0 tbhits 1 {} pv {}",
-                eval_wdl,
-                self.main.tm,
-                res.mov.to_uci(&pos.board.castlingmask)
-            );
+            if !self.silent {
+                self.out.println(format_args!(
+                    "info depth 0 seldepth 0 score {} hashfull 0 tbhits 1 {} pv {}",
+                    eval_wdl,
+                    self.main.tm,
+                    res.mov.to_uci(&pos.board.castlingmask)
+                ));
+            }
 
             return res.mov;
         }
@@ -80,23 +125,35 @@ impl ThreadPool {
         let halfmoves = pos.board.state.halfmoves;
 
         self.main.tm = TimeManager::new(self.global_stop.clone(), self.global_nodes.clone(), tc, pos.stm());
+        self.main.tm.boost_opening(self.game_searches);
+        self.game_searches += 1;
 
-        // Prepare main thread.
-        self.main.prepare_search(halfmoves);
-
-        // Prepare workers.
-        self.workers.iter_mut().for_each(|t| t.prepare_search(halfmoves));
+        // Prepare all threads.
+        for t in iter::once(&mut self.main).chain(self.workers.iter_mut()) {
+            t.prepare_search(halfmoves);
+            t.deterministic = self.deterministic;
+            t.debug = self.debug;
+            t.silent = self.silent;
+            t.out = self.out.clone();
+            t.contempt = self.contempt;
+            t.shared_best = self.shared_best.clone();
+        }
 
         // Store limits.
-        self.global_stop.store(false, Ordering::SeqCst);
+        // The stop flag is cleared when the previous search ends rather than here, so a stop
+        // that arrives before the search has started is not lost.
         self.global_nodes.store(0, Ordering::SeqCst);
+        self.shared_best.store(0, Ordering::SeqCst);
     }
 
     /// Deploys all threads searching in the given position.
     fn deploy_threads(&mut self, pos: &mut Position, tt: &TT, tb: &SyzygyTB) {
+        pos.board.reserve_history();
+
         thread::scope(|scope| {
             for worker in &mut self.workers {
                 let mut worker_pos = pos.clone();
+                worker_pos.board.reserve_history();
                 scope.spawn(move || {
                     worker_pos.iterative_deepening::<false>(worker, tt, tb);
                 });
@@ -105,6 +162,8 @@ impl ThreadPool {
             pos.iterative_deepening::<true>(&mut self.main, tt, tb);
             self.global_stop.store(true, Ordering::Relaxed);
         });
+
+        self.global_stop.store(false, Ordering::SeqCst);
     }
 
     /// Selects the best move from all the threads after they have searched.
@@ -123,3 +182,210 @@ impl ThreadPool {
         move_counts.into_iter().max_by_key(|&(_, count)| count).map_or(Move::NONE, |(mv, _)| mv)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chess::{
+        defs::MAX_PLY,
+        movegen::MoveList,
+        types::{board::Board, moves::MoveFlag, square::Square},
+    };
+
+    use super::*;
+    use crate::tt::entry::Bound;
+
+    #[test]
+    fn test_deterministic_nodes() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        pool.deterministic = true;
+
+        let mut run = || {
+            let tt = TT::default();
+            let mut pos: Position = "startpos".parse().unwrap();
+            pool.reset();
+            pool.go(&mut pos, TimeControl::FixedDepth(8), &tt, &SyzygyTB::default());
+            pool.main.nodes
+        };
+
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_recapture_extension_bounded() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        pool.deterministic = true;
+
+        // Lots of pieces bearing on d5, so there are long recapture sequences.
+        let tt = TT::default();
+        let mut pos: Position = "fen 3r2k1/1b3ppp/2n1qn2/3p4/3P4/2N1QN2/1B3PPP/3R2K1 w - - 0 1".parse().unwrap();
+        let m = pool.go(&mut pos, TimeControl::FixedDepth(10), &tt, &SyzygyTB::default());
+
+        assert!(pos.board.is_legal(m));
+        assert!(pool.main.seldepth < MAX_PLY);
+        assert!(pool.main.nodes < 2_000_000);
+    }
+
+    #[test]
+    fn test_check_extension_finds_mate() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        pool.deterministic = true;
+
+        // Mate in 3 (5 plies): 1. Nf7+ Kg8 2. Nh6+ Kh8 3. Qg8#, as the f8 bishop stops Rxg8.
+        // Every white move is a quiet check, which qsearch does not generate, so without extending the
+        // in-check replies the mating move would only be searched from depth 5.
+        let tt = TT::default();
+        let mut pos: Position = "fen r4b1k/6pp/8/6N1/2Q5/8/8/6K1 w - - 0 1".parse().unwrap();
+        let m = pool.go(&mut pos, TimeControl::FixedDepth(3), &tt, &SyzygyTB::default());
+
+        assert_eq!(m.to_uci(&pos.board.castlingmask), "g5f7");
+        assert_eq!(pool.main.eval, Eval::search_mate_in(5));
+    }
+
+    #[test]
+    fn test_debug_output_only() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        pool.deterministic = true;
+
+        let mut run = |debug| {
+            let tt = TT::default();
+            let mut pos: Position = "kiwipete".parse().unwrap();
+            pool.reset();
+            pool.debug = debug;
+            let m = pool.go(&mut pos, TimeControl::FixedDepth(8), &tt, &SyzygyTB::default());
+            (m, pool.main.nodes)
+        };
+
+        assert_eq!(run(false), run(true));
+    }
+
+    #[test]
+    fn test_silent_search() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        pool.deterministic = true;
+
+        let mut run = |silent| {
+            let tt = TT::default();
+            let lines = Arc::new(Mutex::new(Vec::new()));
+            let mut pos: Position = "kiwipete".parse().unwrap();
+            pool.reset();
+            pool.silent = silent;
+            pool.out = SearchOutput::Buffer(lines.clone());
+            let m = pool.go(&mut pos, TimeControl::FixedDepth(8), &tt, &SyzygyTB::default());
+            assert!(pos.board.is_legal(m));
+            ((m, pool.main.eval, pool.main.depth, pool.main.nodes), lines.lock().unwrap().clone())
+        };
+
+        let (silent, silent_lines) = run(true);
+        let (loud, loud_lines) = run(false);
+
+        // Nothing is written in silent mode, but the search itself must not change.
+        assert!(silent_lines.is_empty());
+        assert!(loud_lines.iter().any(|l| l.starts_with("info depth 8 ")));
+        assert_eq!(silent, loud);
+    }
+
+    #[test]
+    fn test_no_time_left() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        let tt = TT::default();
+        let mut pos: Position = "kiwipete".parse().unwrap();
+
+        let tc: TimeControl = "wtime 1 btime 1".parse().unwrap();
+        let m = pool.go(&mut pos, tc, &tt, &SyzygyTB::default());
+
+        // Even if the first iteration is cut short, we still play a legal move.
+        assert!(!m.is_none());
+        assert!(pos.board.is_legal(m));
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        let search = |pool: &mut ThreadPool, fen: &str| {
+            let tt = TT::default();
+            let mut pos: Position = fen.parse().unwrap();
+            let m = pool.go(&mut pos, TimeControl::FixedDepth(8), &tt, &SyzygyTB::default());
+            (m, pool.main.nodes)
+        };
+
+        let mut fresh = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        let expected = search(&mut fresh, "startpos");
+
+        // Play another game first, then reset as we would for ucinewgame.
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        search(&mut pool, "kiwipete");
+        pool.reset();
+
+        assert_eq!(search(&mut pool, "startpos"), expected);
+    }
+
+    #[test]
+    fn test_immediate_stop_returns_legal_move() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        let tt = TT::default();
+
+        for fen in ["startpos", "kiwipete", "fen 7k/8/6K1/8/8/8/8/R7 w - - 0 1"] {
+            let mut pos: Position = fen.parse().unwrap();
+            let m = pool.go(&mut pos, TimeControl::FixedNodes(1), &tt, &SyzygyTB::default());
+            assert!(!m.is_none());
+            assert!(pos.board.is_legal(m));
+        }
+    }
+
+    #[test]
+    fn test_bogus_tt_move_ignored() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        let tt = TT::default();
+        let mut pos: Position = "startpos".parse().unwrap();
+
+        // Simulate a key collision handing us moves that can't be played here.
+        for m in [Move::new(Square::E7, Square::E5, MoveFlag::DoublePush), Move::new(Square::D1, Square::H5, MoveFlag::Normal)] {
+            assert!(!pos.board.is_legal(m));
+            tt.insert(pos.hash(), Bound::Exact, m, Eval::DRAW, Eval(500), 20, 0, true);
+
+            let best = pool.go(&mut pos, TimeControl::FixedDepth(4), &tt, &SyzygyTB::default());
+            assert!(pos.board.is_legal(best));
+        }
+    }
+
+    #[test]
+    fn test_resize_copies_histories() {
+        let b: Board = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+        let e2e4 = b.find_move("e2e4").unwrap();
+        let d2d4 = b.find_move("d2d4").unwrap();
+
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        pool.main.update_history(e2e4, 8, &b, &MoveList::new(), &MoveList::new());
+        assert_ne!(pool.main.hist_quiet.get_bonus(b.stm, e2e4), 0);
+
+        pool.resize(3);
+        for w in &pool.workers {
+            for m in [e2e4, d2d4] {
+                assert_eq!(w.hist_quiet.get_bonus(b.stm, m), pool.main.hist_quiet.get_bonus(b.stm, m));
+            }
+        }
+
+        // Shrinking keeps the remaining threads as they were.
+        pool.workers[0].update_history(d2d4, 8, &b, &MoveList::new(), &MoveList::new());
+        pool.resize(1);
+        assert_ne!(pool.workers[0].hist_quiet.get_bonus(b.stm, d2d4), 0);
+        assert_ne!(pool.main.hist_quiet.get_bonus(b.stm, e2e4), 0);
+    }
+
+    #[test]
+    fn test_go_terminal_position() {
+        let mut pool = ThreadPool::new(Arc::new(AtomicBool::new(false)));
+        let tt = TT::default();
+
+        for (fen, result) in
+            [("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", GameResult::Checkmate), ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", GameResult::Stalemate)]
+        {
+            let mut pos: Position = format!("fen {fen}").parse().unwrap();
+            let m = pool.go(&mut pos, TimeControl::FixedDepth(4), &tt, &SyzygyTB::default());
+
+            assert!(m.is_none());
+            assert_eq!(pool.game_result, Some(result));
+        }
+    }
+}
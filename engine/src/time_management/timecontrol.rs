@@ -19,6 +19,7 @@ pub enum TimeControl {
         winc: Option<u64>,      // White increment.
         binc: Option<u64>,      // Black increment.
         movestogo: Option<u64>, // Number of moves until increment.
+        nodestime: Option<u64>, // Nodes per millisecond to use as a clock.
     },
 }
 
@@ -38,6 +39,7 @@ impl FromStr for TimeControl {
         let mut winc:      Option<u64> = None;
         let mut binc:      Option<u64> = None;
         let mut movestogo: Option<u64> = None;
+        let mut nodestime: Option<u64> = None;
 
         let mut tokens = s.split_whitespace();
         while let Some(token) = tokens.next() {
@@ -54,14 +56,19 @@ impl FromStr for TimeControl {
                 "winc"      => winc  = Some(parse(&mut tokens)?),
                 "binc"      => binc  = Some(parse(&mut tokens)?),
                 "movestogo" => movestogo = Some(parse(&mut tokens)?),
+                "nodestime" => nodestime = Some(parse(&mut tokens)?),
 
                 // Unimplemented.
                 _ => return Err("Unknown token in time control!"),
             }
         }
 
+        if nodestime == Some(0) {
+            return Err("Invalid nodestime!");
+        }
+
         if let (Some(wtime), Some(btime)) = (wtime, btime) {
-            Ok(Self::Variable { wtime, btime, winc, binc, movestogo })
+            Ok(Self::Variable { wtime, btime, winc, binc, movestogo, nodestime })
         } else {
             Err("Invalid time control!")
         }
@@ -92,7 +99,7 @@ impl TimeControl {
             }
 
             // Variable time control.
-            Self::Variable { wtime, btime, winc, binc, movestogo } => {
+            Self::Variable { wtime, btime, winc, binc, movestogo, .. } => {
                 let (mut time, mut inc) = match stm {
                     Color::White => (wtime, winc.unwrap_or(0)),
                     Color::Black => (btime, binc.unwrap_or(0)),
@@ -125,3 +132,19 @@ impl TimeControl {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nodestime() {
+        let tc: TimeControl = "wtime 1000 btime 1000 nodestime 500".parse().unwrap();
+        assert!(matches!(tc, TimeControl::Variable { nodestime: Some(500), .. }));
+
+        let tc: TimeControl = "wtime 1000 btime 1000".parse().unwrap();
+        assert!(matches!(tc, TimeControl::Variable { nodestime: None, .. }));
+
+        assert!("wtime 1000 btime 1000 nodestime 0".parse::<TimeControl>().is_err());
+    }
+}
@@ -9,7 +9,10 @@ use std::{
 
 use chess::types::{Depth, color::Color, moves::Move, square::Square};
 
-use crate::time_management::timecontrol::TimeControl;
+use crate::{
+    time_management::timecontrol::TimeControl,
+    tunables::params::tunables::{tm_opening_boost, tm_opening_moves},
+};
 
 #[derive(Clone, Debug)]
 pub struct TimeManager {
@@ -26,6 +29,8 @@ pub struct TimeManager {
     // Thread-specific.
     last_check: u64,
     move_nodes: [[u64; Square::NUM]; Square::NUM],
+    prev_best: Move,
+    best_move_changes: f64,
 }
 
 /// Display time used in UCI format.
@@ -43,13 +48,27 @@ impl TimeManager {
     /// Check time after this many nodes.
     const FREQUENCY: u64 = 2048;
 
+    /// Extra time to use per recent best move change.
+    const INSTABILITY_MULT: f64 = 0.5;
+
     /// Initialize a new time manager.
     #[allow(clippy::large_stack_arrays)]
     pub fn new(global_stop: Arc<AtomicBool>, global_nodes: Arc<AtomicU64>, tc: TimeControl, stm: Color) -> Self {
         let (soft_bound, hard_bound) = tc.get_time_bounds(stm);
         let start = Instant::now();
 
-        Self { start, tc, soft_bound, hard_bound, global_stop, global_nodes, last_check: 0, move_nodes: [[0; Square::NUM]; Square::NUM] }
+        Self {
+            start,
+            tc,
+            soft_bound,
+            hard_bound,
+            global_stop,
+            global_nodes,
+            last_check: 0,
+            move_nodes: [[0; Square::NUM]; Square::NUM],
+            prev_best: Move::NONE,
+            best_move_changes: 0.0,
+        }
     }
 
     /// Change the time controls.
@@ -62,8 +81,15 @@ impl TimeManager {
         self.global_nodes.store(0, Ordering::SeqCst);
     }
 
+    /// Spend a bit more time on the first few searches of a game, as early decisions compound.
+    /// This only applies when the time has to last for the rest of the game.
+    pub fn boost_opening(&mut self, searches: usize) {
+        if searches < tm_opening_moves() && matches!(self.tc, TimeControl::Variable { movestogo: None, .. }) {
+            self.soft_bound = self.soft_bound.mul_f32(1.0 + tm_opening_boost()).min(self.hard_bound);
+        }
+    }
+
     /// Whether we should start the given iteration.
-    #[allow(clippy::cast_precision_loss)]
     pub fn should_start_iter(&mut self, depth: Depth, nodes: u64, best_move: Move) -> bool {
         if self.is_stopped() {
             return false;
@@ -80,17 +106,7 @@ impl TimeManager {
             TimeControl::FixedNodes(n) => self.global_nodes() <= n,
 
             // Time related time controls (opt and max set).
-            _ => {
-                let scale = if !best_move.is_none() && nodes != 0 {
-                    let f = self.move_nodes[best_move.src().idx()][best_move.dst().idx()] as f64 / nodes as f64;
-
-                    (0.4 + (1.0 - f) * 2.0).max(0.5)
-                } else {
-                    1.0
-                };
-
-                self.elapsed() < self.soft_bound.mul_f64(scale)
-            }
+            _ => self.elapsed() < self.soft_limit(nodes, best_move),
         };
 
         // If we should stop, tell the other threads to also stop.
@@ -101,13 +117,21 @@ impl TimeManager {
         should_start
     }
 
+    /// Whether we have used up the optimal time for this search.
+    /// Unlike [`Self::should_start_iter`], this can be checked in the middle of an iteration.
+    pub fn soft_expired(&self, nodes: u64, best_move: Move) -> bool {
+        match self.tc {
+            TimeControl::Variable { .. } | TimeControl::FixedTime(_) => self.elapsed() >= self.soft_limit(nodes, best_move),
+            _ => false,
+        }
+    }
+
     /// Whether we should continue an ongoing search.
     pub fn should_continue(&mut self, nodes: u64) -> bool {
         let delta = nodes - self.last_check;
 
         if delta >= Self::FREQUENCY {
-            self.global_nodes.fetch_add(delta, Ordering::Relaxed);
-            self.last_check = nodes;
+            self.sync_nodes(nodes);
             if self.is_stopped() {
                 return false;
             }
@@ -138,8 +162,19 @@ impl TimeManager {
     }
 
     /// The total elapsed time since we started searching.
+    /// With nodestime, this is simulated from the number of nodes searched rather than the clock.
     pub fn elapsed(&self) -> Duration {
-        self.start.elapsed()
+        match self.tc {
+            TimeControl::Variable { nodestime: Some(npms), .. } => Duration::from_millis(self.global_nodes() / npms),
+            _ => self.start.elapsed(),
+        }
+    }
+
+    /// Add any nodes searched since the last check to the global node count.
+    /// Small searches may never reach a check, so this should be done before reporting nodes.
+    pub fn sync_nodes(&mut self, nodes: u64) {
+        self.global_nodes.fetch_add(nodes - self.last_check, Ordering::Relaxed);
+        self.last_check = nodes;
     }
 
     /// Update the node count for the given move.
@@ -147,13 +182,177 @@ impl TimeManager {
         self.move_nodes[m.src().idx()][m.dst().idx()] += nodes_searched;
     }
 
+    /// The number of nodes spent searching the given move.
+    pub const fn move_nodes(&self, m: Move) -> u64 {
+        self.move_nodes[m.src().idx()][m.dst().idx()]
+    }
+
     /// Raise the stop flag to tell all threads to stop searching now.
     pub fn raise_stop(&mut self) {
         self.global_stop.store(true, Ordering::Relaxed);
     }
 
     /// Prepare the timemanager for a search.
-    pub fn prepare_search(&mut self) {
+    pub const fn prepare_search(&mut self) {
         self.last_check = 0;
+        self.move_nodes = [[0; Square::NUM]; Square::NUM];
+        self.prev_best = Move::NONE;
+        self.best_move_changes = 0.0;
+    }
+
+    /// Report the best move after completing an iteration.
+    /// Older changes are decayed so that only recent instability matters.
+    pub fn update_best_move(&mut self, m: Move) {
+        self.best_move_changes /= 2.0;
+        if !self.prev_best.is_none() && m != self.prev_best {
+            self.best_move_changes += 1.0;
+        }
+        self.prev_best = m;
+    }
+
+    /// How much to scale the optimal time by, based on how much effort has gone into the best move
+    /// and how stable it has been.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn time_scale(&self, nodes: u64, best_move: Move) -> f64 {
+        let scale = if !best_move.is_none() && nodes != 0 {
+            let f = self.move_nodes[best_move.src().idx()][best_move.dst().idx()] as f64 / nodes as f64;
+
+            (0.4 + (1.0 - f) * 2.0).max(0.5)
+        } else {
+            1.0
+        };
+
+        scale * self.instability()
+    }
+
+    /// The optimal time to use for this search.
+    fn soft_limit(&self, nodes: u64, best_move: Move) -> Duration {
+        self.soft_bound.mul_f64(self.time_scale(nodes, best_move)).min(self.hard_bound)
+    }
+
+    /// Time scale factor for how often the best move has been changing.
+    fn instability(&self) -> f64 {
+        Self::INSTABILITY_MULT.mul_add(self.best_move_changes, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::types::moves::MoveFlag;
+
+    use super::*;
+
+    #[test]
+    fn test_prepare_resets_move_nodes() {
+        let a = Move::new(Square::E2, Square::E4, MoveFlag::DoublePush);
+        let mut tm = TimeManager::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(0)), TimeControl::Infinite, Color::White);
+
+        // Node counts from the last search should not leak into root ordering for the next one.
+        tm.update_nodes(a, 1000);
+        assert_eq!(tm.move_nodes(a), 1000);
+        tm.prepare_search();
+        assert_eq!(tm.move_nodes(a), 0);
+    }
+
+    #[test]
+    fn test_nodestime_elapsed() {
+        let global_nodes = Arc::new(AtomicU64::new(0));
+        let tc: TimeControl = "wtime 10000 btime 10000 nodestime 100".parse().unwrap();
+        let tm = TimeManager::new(Arc::new(AtomicBool::new(false)), global_nodes.clone(), tc, Color::White);
+
+        assert_eq!(tm.elapsed(), Duration::ZERO);
+
+        global_nodes.store(150_000, Ordering::Relaxed);
+        assert_eq!(tm.elapsed(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_instability_extends_time() {
+        let a = Move::new(Square::E2, Square::E4, MoveFlag::DoublePush);
+        let b = Move::new(Square::D2, Square::D4, MoveFlag::DoublePush);
+
+        // Use nodestime so that elapsed time is exactly 400ms: between the soft and hard bounds.
+        let tc: TimeControl = "wtime 10000 btime 10000 nodestime 1".parse().unwrap();
+        let make_tm = |nodes| TimeManager::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(nodes)), tc, Color::White);
+
+        // Stable best move: out of time.
+        let mut tm = make_tm(400);
+        for _ in 0..4 {
+            tm.update_best_move(a);
+        }
+        assert!(!tm.should_start_iter(5, 0, a));
+
+        // Best move keeps changing: keep searching.
+        let mut tm = make_tm(400);
+        for m in [a, b, a, b] {
+            tm.update_best_move(m);
+        }
+        assert!(tm.instability() > 1.0);
+        assert!(tm.should_start_iter(5, 0, b));
+
+        // Never go past the hard bound, no matter how unstable.
+        let mut tm = make_tm(10_000);
+        for m in [a, b, a, b, a, b, a, b] {
+            tm.update_best_move(m);
+        }
+        assert!(!tm.should_start_iter(5, 0, b));
+    }
+
+    #[test]
+    fn test_soft_expired() {
+        let a = Move::new(Square::E2, Square::E4, MoveFlag::DoublePush);
+
+        // 400ms is past the soft bound but well within the hard bound.
+        let tc: TimeControl = "wtime 10000 btime 10000 nodestime 1".parse().unwrap();
+        let mut tm = TimeManager::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(400)), tc, Color::White);
+        assert!(tm.soft_expired(0, a));
+        assert!(tm.should_continue(0));
+
+        let tm = TimeManager::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(10)), tc, Color::White);
+        assert!(!tm.soft_expired(0, a));
+
+        // Non time related controls never expire.
+        let tm =
+            TimeManager::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(400)), TimeControl::FixedDepth(5), Color::White);
+        assert!(!tm.soft_expired(0, a));
+    }
+
+    #[test]
+    fn test_opening_boost() {
+        let make_tm = |tc| TimeManager::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(0)), tc, Color::White);
+        let tc: TimeControl = "wtime 10000 btime 10000".parse().unwrap();
+        let base = make_tm(tc).soft_bound;
+
+        // Early in the game we spend more, but never more than the hard bound.
+        let mut tm = make_tm(tc);
+        tm.boost_opening(0);
+        assert!(tm.soft_bound > base);
+        assert!(tm.soft_bound <= tm.hard_bound);
+
+        let mut tm = make_tm(tc);
+        tm.boost_opening(tm_opening_moves());
+        assert_eq!(tm.soft_bound, base);
+
+        // With a known number of moves to go, the allocation is left alone.
+        let tc: TimeControl = "wtime 10000 btime 10000 movestogo 20".parse().unwrap();
+        let base = make_tm(tc).soft_bound;
+        let mut tm = make_tm(tc);
+        tm.boost_opening(0);
+        assert_eq!(tm.soft_bound, base);
+    }
+
+    #[test]
+    fn test_display_without_time_limits() {
+        for tc in [TimeControl::FixedDepth(1), TimeControl::Infinite] {
+            let mut tm = TimeManager::new(Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(0)), tc, Color::White);
+
+            // Fewer nodes than we would normally check the time after.
+            tm.sync_nodes(100);
+            let info = tm.to_string();
+            let nps: u64 = info.split_whitespace().skip_while(|&s| s != "nps").nth(1).unwrap().parse().unwrap();
+
+            assert!(info.starts_with("nodes 100 "));
+            assert!(nps > 0);
+        }
     }
 }
@@ -13,7 +13,7 @@ pub const TT_DEPTH_UNSEARCHED: Depth = -2;
 pub const TT_DEPTH_OFFSET: Depth = 3;
 
 /// Number of entries to store in each bucket.
-pub const TT_BUCKET_ENTRIES: usize = 3;
+pub const TT_BUCKET_ENTRIES: usize = 4;
 
 /// Size of each TT bucket.
 pub const TT_BUCKET_SIZE: usize = size_of::<TTBucket>();
@@ -22,8 +22,11 @@ const TT_BUCKET_WORDS: usize = TT_BUCKET_SIZE / size_of::<u64>();
 pub const TT_AGE_CYCLE: u8 = 1 << 5;
 pub const TT_AGE_MASK: u8 = TT_AGE_CYCLE - 1;
 
-/// Penalty applied to older entries when choosing a replacement victim.
-pub const TT_AGE_MUL: i32 = 8;
+/// Bonus applied to entries from the current generation when choosing a replacement victim.
+pub const TT_AGE_BONUS: i32 = 2;
+
+/// Entries at least this many generations old are treated as shallower when probed.
+pub const TT_STALE_AGE: u8 = 8;
 
 /// Get the partial key stored in each entry.
 pub const fn get_low_16(hash: Hash) -> u16 {
@@ -136,28 +139,35 @@ impl TTEntry {
         self.is_occupied() && self.key_matches(hash)
     }
 
-    /// Age distance from the current table generation, modulo the age cycle.
-    pub const fn relative_age(&self, tt_age: u8) -> i32 {
-        ((TT_AGE_CYCLE + tt_age - self.metadata.age()) & TT_AGE_MASK) as i32
+    /// Number of generations since this entry was written.
+    pub const fn age_delta(&self, tt_age: u8) -> u8 {
+        tt_age.wrapping_sub(self.metadata.age()) & TT_AGE_MASK
     }
 
     /// Replacement priority. Lower values are replaced first.
     pub const fn relative_quality(&self, tt_age: u8) -> i32 {
-        self.depth as i32 - TT_AGE_MUL * self.relative_age(tt_age)
+        self.depth as i32 + TT_AGE_BONUS * (self.metadata.age() == tt_age) as i32
     }
 }
 
 /// One cache-line-sized bucket of TT entries.
 #[derive(Clone, Copy, Debug, Default)]
-#[repr(C, align(32))]
+#[repr(C, align(64))]
 pub struct TTBucket {
-    pub entries: [TTEntry; TT_BUCKET_ENTRIES], // 30 bytes
+    pub entries: [TTEntry; TT_BUCKET_ENTRIES], // 40 bytes
     hash: u16,                                 // 2 bytes
+    _pad: [u16; 11],                           // 22 bytes
 }
 
 impl TTBucket {
     const fn checksum(&self) -> u16 {
-        self.entries[0].key ^ self.entries[1].key ^ self.entries[2].key
+        let mut hash = 0;
+        let mut i = 0;
+        while i < TT_BUCKET_ENTRIES {
+            hash ^= self.entries[i].key;
+            i += 1;
+        }
+        hash
     }
 
     pub const fn update_checksum(&mut self) {
@@ -171,13 +181,13 @@ impl TTBucket {
 
 /// Atomic bucket storage.
 #[derive(Debug, Default)]
-#[repr(C, align(32))]
+#[repr(C, align(64))]
 pub struct AtomicTTBucket {
-    data: [AtomicU64; TT_BUCKET_WORDS], // 32 bytes
+    data: [AtomicU64; TT_BUCKET_WORDS], // 64 bytes
 }
 
-const _: () = assert!(size_of::<TTBucket>() == 32);
-const _: () = assert!(size_of::<AtomicTTBucket>() == 32);
+const _: () = assert!(size_of::<TTBucket>() == 64);
+const _: () = assert!(size_of::<AtomicTTBucket>() == 64);
 
 impl AtomicTTBucket {
     pub fn load(&self) -> TTBucket {
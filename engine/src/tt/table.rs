@@ -1,7 +1,12 @@
+use std::fmt;
+
 use chess::types::{Depth, eval::Eval, moves::Move, zobrist::Hash};
 
 use crate::{
-    tt::entry::{AtomicTTBucket, Bound, TT_AGE_MASK, TT_BUCKET_SIZE, TT_DEPTH_OFFSET, TTBucket, TTEntry, TTMetadata, get_low_16},
+    tt::entry::{
+        AtomicTTBucket, Bound, TT_AGE_CYCLE, TT_AGE_MASK, TT_BUCKET_ENTRIES, TT_BUCKET_SIZE, TT_DEPTH_OFFSET, TT_STALE_AGE, TTBucket,
+        TTEntry, TTMetadata, get_low_16,
+    },
     tunables::params::tunables::tt_replace_d_min,
 };
 
@@ -13,6 +18,52 @@ pub struct TT {
 
 const MEGABYTE: usize = 1024 * 1024;
 
+/// Number of buckets sampled for [`TT::stats`].
+const STATS_SAMPLE: usize = 1000;
+
+/// Summary of what is stored in a sample of the TT.
+#[derive(Clone, Debug, Default)]
+pub struct TTStats {
+    pub buckets: usize,
+    pub occupied: usize,
+    pub avg_depth: f64,
+    pub bad_checksums: usize,
+    pub age_deltas: [usize; TT_AGE_CYCLE as usize],
+}
+
+impl TTStats {
+    /// Estimated chance that a probe matches an entry stored for a different position.
+    /// Only the low 16 bits of the key are stored, so any occupied entry in the bucket could match.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn collision_rate(&self) -> f64 {
+        if self.buckets == 0 {
+            return 0.0;
+        }
+
+        self.occupied as f64 / self.buckets as f64 / 65536.0
+    }
+}
+
+impl fmt::Display for TTStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = self.buckets * TT_BUCKET_ENTRIES;
+
+        writeln!(f, "{:=^1$}", " TT stats ", 25)?;
+        writeln!(f, "  sampled:   {entries} entries")?;
+        writeln!(f, "  occupied:  {}", self.occupied)?;
+        writeln!(f, "  avg depth: {:.2}", self.avg_depth)?;
+        writeln!(f, "  torn:      {} buckets", self.bad_checksums)?;
+        writeln!(f, "  collision: {:.6}%", self.collision_rate() * 100.0)?;
+        writeln!(f, "  age:")?;
+
+        for (delta, n) in self.age_deltas.iter().enumerate().filter(|&(_, &n)| n > 0) {
+            writeln!(f, "    -{delta:<2} {n}")?;
+        }
+
+        write!(f, "{:=^1$}", " <> ", 25)
+    }
+}
+
 impl Default for TT {
     fn default() -> Self {
         Self::with_size(Self::DEFAULT_SIZE_MB)
@@ -53,6 +104,35 @@ impl TT {
         self.buckets.iter().flat_map(|bucket| bucket.load().entries).take(1000).filter(|entry| entry.is_occupied()).count()
     }
 
+    /// Gather statistics from a sample of buckets spread across the table.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn stats(&self) -> TTStats {
+        let mut stats = TTStats::default();
+        let mut total_depth = 0;
+        let step = (self.buckets.len() / STATS_SAMPLE).max(1);
+
+        for bucket in self.buckets.iter().step_by(step).take(STATS_SAMPLE).map(AtomicTTBucket::load) {
+            stats.buckets += 1;
+
+            if !bucket.checksum_matches() {
+                stats.bad_checksums += 1;
+                continue;
+            }
+
+            for entry in bucket.entries.iter().filter(|entry| entry.is_occupied()) {
+                stats.occupied += 1;
+                stats.age_deltas[usize::from(entry.age_delta(self.age))] += 1;
+                total_depth += i32::from(entry.depth());
+            }
+        }
+
+        if stats.occupied > 0 {
+            stats.avg_depth = f64::from(total_depth) / stats.occupied as f64;
+        }
+
+        stats
+    }
+
     /// Map a full hash to a bucket index using high-multiply reduction.
     const fn idx(&self, hash: Hash) -> usize {
         let key = hash.key as u128;
@@ -73,6 +153,8 @@ impl TT {
     }
 
     /// Probe the TT for an entry matching the given hash.
+    /// Very old entries lose a ply of depth for every generation they have been around,
+    /// so deep results from an earlier search are not trusted for cutoffs.
     pub fn probe(&self, hash: Hash) -> Option<TTEntry> {
         let bucket: TTBucket = self.buckets[self.idx(hash)].load();
 
@@ -80,7 +162,14 @@ impl TT {
             return None;
         }
 
-        bucket.entries.iter().find(|&entry| entry.matches(hash)).copied()
+        let mut entry = bucket.entries.iter().find(|&entry| entry.matches(hash)).copied()?;
+
+        let delta = entry.age_delta(self.age);
+        if delta >= TT_STALE_AGE {
+            entry.depth = entry.depth.saturating_sub(delta).max(1);
+        }
+
+        Some(entry)
     }
 
     /// Insert or update an entry.
@@ -132,9 +221,12 @@ impl TT {
 
 #[cfg(test)]
 mod tests {
-    use chess::types::{eval::Eval, moves::Move, zobrist::Hash};
+    use chess::types::{Depth, eval::Eval, moves::Move, zobrist::Hash};
 
-    use crate::tt::{entry::Bound, table::TT};
+    use crate::tt::{
+        entry::{Bound, TT_AGE_MASK, TT_BUCKET_ENTRIES, TT_DEPTH_OFFSET, TT_DEPTH_UNSEARCHED, TT_STALE_AGE},
+        table::TT,
+    };
 
     #[test]
     fn test_insert_roundtrip() {
@@ -151,4 +243,133 @@ mod tests {
         assert_eq!(Bound::Lower, entry.bound());
         assert!(entry.pv());
     }
+
+    #[test]
+    fn test_static_eval_reused() {
+        let tt = TT::with_size(1);
+        let h = Hash { key: 0x1234_5678_9ABC_DEF0, ..Hash::default() };
+
+        // The search stores the raw eval of unsearched positions (including null move children)
+        // with no bound, and reuses it on any later visit.
+        tt.insert(h, Bound::None, Move::NONE, Eval(77), -Eval::INFINITY, TT_DEPTH_UNSEARCHED, 0, false);
+        let entry = tt.probe(h).unwrap();
+        assert_eq!(Eval(77), entry.eval());
+        assert!(entry.depth() > -TT_DEPTH_OFFSET);
+        assert!(!entry.bound().is_usable(entry.value(0), Eval::DRAW));
+
+        // Search results for the same position keep the eval.
+        tt.insert(h, Bound::Upper, Move::NONE, entry.eval(), Eval(-50), 3, 0, false);
+        assert_eq!(Eval(77), tt.probe(h).unwrap().eval());
+    }
+
+    #[test]
+    fn test_deep_entry_survives_shallow_inserts() {
+        let tt = TT::with_size(1);
+
+        // All of these keys only differ in their low bits, so they map to the same bucket.
+        let deep = Hash { key: 0x1234_5678_9ABC_0000, ..Hash::default() };
+        tt.insert(deep, Bound::Exact, Move(42), Eval(10), Eval(20), 20, 0, true);
+
+        for i in 1..=8 {
+            let shallow = Hash { key: deep.key + i, ..Hash::default() };
+            assert_eq!(tt.idx(shallow), tt.idx(deep));
+            tt.insert(shallow, Bound::Upper, Move::NONE, Eval(0), Eval(0), 1, 0, false);
+        }
+
+        let entry = tt.probe(deep).unwrap();
+        assert_eq!(Move(42), entry.mov());
+        assert_eq!(20, entry.depth());
+        assert_eq!(Bound::Exact, entry.bound());
+    }
+
+    #[test]
+    fn test_old_entry_replaced_first() {
+        let mut tt = TT::with_size(1);
+
+        let old = Hash { key: 0x1234_5678_9ABC_0000, ..Hash::default() };
+        tt.insert(old, Bound::Exact, Move(42), Eval(10), Eval(20), 6, 0, false);
+        tt.increment_age();
+
+        // Fill the rest of the bucket with entries of the same depth from the current search.
+        let fresh: Vec<Hash> = (1..TT_BUCKET_ENTRIES as u64).map(|i| Hash { key: old.key + i, ..Hash::default() }).collect();
+        for &h in &fresh {
+            tt.insert(h, Bound::Upper, Move::NONE, Eval(0), Eval(0), 6, 0, false);
+        }
+
+        // At equal depth, the entry from the previous generation is the victim.
+        let new = Hash { key: old.key + TT_BUCKET_ENTRIES as u64, ..Hash::default() };
+        tt.insert(new, Bound::Upper, Move::NONE, Eval(0), Eval(0), 6, 0, false);
+
+        assert!(tt.probe(old).is_none());
+        assert!(tt.probe(new).is_some());
+        assert!(fresh.iter().all(|&h| tt.probe(h).is_some()));
+    }
+
+    #[test]
+    fn test_stale_entries_are_shallower() {
+        let mut tt = TT::with_size(1);
+        let h = Hash { key: 0x1234_5678_9ABC_DEF0, ..Hash::default() };
+        tt.insert(h, Bound::Exact, Move(42), Eval(10), Eval(20), 20, 0, true);
+
+        // Recent entries are trusted as they are.
+        for _ in 1..TT_STALE_AGE {
+            tt.increment_age();
+        }
+        assert_eq!(20, tt.probe(h).unwrap().depth());
+
+        // Entries from many searches ago lose depth, but keep everything else.
+        tt.increment_age();
+        let entry = tt.probe(h).unwrap();
+        assert_eq!(20 - Depth::from(TT_STALE_AGE), entry.depth());
+        assert_eq!(Move(42), entry.mov());
+        assert_eq!(Bound::Exact, entry.bound());
+
+        // Shallow entries never drop below unsearched.
+        tt.insert(h, Bound::Exact, Move(42), Eval(10), Eval(20), 2, 0, true);
+        for _ in 0..TT_AGE_MASK {
+            tt.increment_age();
+        }
+        assert_eq!(TT_DEPTH_UNSEARCHED, tt.probe(h).unwrap().depth());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut tt = TT::with_size(1);
+        let stats = tt.stats();
+        assert_eq!(stats.buckets, 1000);
+        assert_eq!(stats.occupied, 0);
+        assert_eq!(stats.collision_rate(), 0.0);
+
+        // The first bucket is always sampled.
+        let h = Hash { key: 0, ..Hash::default() };
+        tt.insert(h, Bound::Exact, Move(42), Eval(10), Eval(20), 6, 0, false);
+        tt.increment_age();
+        tt.insert(Hash { key: 1, ..Hash::default() }, Bound::Exact, Move(42), Eval(10), Eval(20), 10, 0, false);
+
+        let stats = tt.stats();
+        assert_eq!(stats.occupied, 2);
+        assert_eq!(stats.avg_depth, 8.0);
+        assert_eq!(stats.age_deltas[0], 1);
+        assert_eq!(stats.age_deltas[1], 1);
+        assert!(stats.collision_rate() > 0.0);
+    }
+
+    #[test]
+    fn test_probe_rejects_foreign_key() {
+        let tt = TT::with_size(1);
+        let h = Hash { key: 0x1234_5678_9ABC_DEF0, ..Hash::default() };
+        tt.insert(h, Bound::Exact, Move(42), Eval(10), Eval(20), 8, 0, false);
+
+        // Same bucket, different signature.
+        let same_bucket = Hash { key: h.key ^ 0x1, ..Hash::default() };
+        assert_eq!(tt.idx(same_bucket), tt.idx(h));
+        assert!(tt.probe(same_bucket).is_none());
+
+        // Same signature, different bucket.
+        let same_sig = Hash { key: h.key ^ 0x8000_0000_0000_0000, ..Hash::default() };
+        assert_ne!(tt.idx(same_sig), tt.idx(h));
+        assert!(tt.probe(same_sig).is_none());
+
+        assert_eq!(Move(42), tt.probe(h).unwrap().mov());
+    }
 }
@@ -31,6 +31,12 @@ init_tunables! {
     ms_rook:   i32 = 705, 600, 800, 10;
     ms_queen:  i32 = 1313, 1200, 1400, 10;
 
+    // Opposite colored bishop endgame scaling (scaled up x1024).
+    ocb_scale: i32 = 640, 256, 1024, 32;
+
+    // 50 move rule scaling: the eval is scaled by (scale - halfmoves) / scale, once past the grace period.
+    fifty_move_scale: i32 = 160, 120, 300, 10;
+
     // Aspiration window.
     asp_window_d_min:     i16 = 4, 2, 7, 1;
     asp_window_div:       i32 = 11400, 8000, 14000, 500;
@@ -39,6 +45,13 @@ init_tunables! {
     asp_window_expansion_fail_high: f32 = 0.34687291, 0.1, 0.5, 0.05;
     asp_window_expansion_fail_low: f32  = 0.32567887, 0.1, 0.5, 0.05;
 
+    // Extra optimal time for the first searches of a game.
+    tm_opening_moves: usize = 8, 0, 16, 1;
+    tm_opening_boost: f32 = 0.2, 0.0, 0.5, 0.05;
+
+    // Root repetition penalty.
+    root_rep_penalty: i32 = 20, 0, 50, 5;
+
     // History bonuses.
     hist_bonus_max:  i32 = 1567, 800, 3200, 100;
     hist_bonus_base: i32 = 356, 100, 600, 25;
@@ -49,18 +62,41 @@ init_tunables! {
     hist_malus_base: i32 = 356, 100, 600, 25;
     hist_malus_mult: i32 = 366, 100, 600, 25;
 
+    // Malus for a quiet TT move that fails low (scaled x1024).
+    // Off until a bench / SPRT result justifies enabling it.
+    hist_tt_fail_low: i32 = 0, 0, 1024, 64;
+
     // History divisors.
     hist_quiet_div: i32 = 8867, 7000, 10000, 150;
     hist_noisy_div: i32 = 6329, 5000, 8000, 150;
 
+    // Continuation history weights, one per continuation ply. (scaled up x1024).
+    ch_scale_0: i32 = 1024, 512, 1536, 32;
+    ch_scale_1: i32 = 1024, 512, 1536, 32;
+    ch_scale_2: i32 = 1024, 0, 1536, 32;
+    ch_scale_3: i32 = 1024, 0, 1536, 32;
+
+    // Capture continuation history weight. (scaled up x1024).
+    cch_scale: i32 = 1024, 0, 2048, 64;
+
     // Correction history weights. (scaled up x1024).
     hist_corr_pawn:  i32 = 80, 60, 100, 2;
     hist_corr_other: i32 = 100, 80, 120, 2;
 
+    // Correction history bonus, growing quadratically with depth. (scaled up x1024).
+    hist_corr_bonus_base:  i32 = 128, 64, 256, 8;
+    hist_corr_bonus_depth: i32 = 8, 0, 32, 2;
+
+    // Maximum total correction applied to the static eval.
+    hist_corr_max: i32 = 128, 64, 256, 8;
+
     // transposition table.
     tt_replace_d_min: i16 = 4, 2, 6, 1;
 
     // Probcut.
+    pc_d_min:     i16 = 5, 3, 8, 1;
+    pc_depth_red: i16 = 4, 2, 6, 1;
+
     pc_beta_base:          i32 = 143, 120, 200, 5;
     pc_beta_non_improving: i32 = 55, 30, 80, 4;
 
@@ -71,9 +107,11 @@ init_tunables! {
     ext_mult:   i16 = 2, 1, 4, 1;
     ext_double: i32 = 12, 7, 14, 1;
     ext_triple: i32 = 80, 50, 150, 20;
+    ext_recap_d_max: i16 = 6, 3, 10, 1;
 
-    // Late move reductions.
-    lmr_m_min: usize = 2, 1, 4, 1;
+    // Late move reductions: moves to try before reducing.
+    lmr_min_moves_pv:    usize = 3, 1, 6, 1;
+    lmr_min_moves_nonpv: usize = 2, 1, 6, 1;
 
     // Late move reduction table parameters.
     lmr_base: f32 = 0.95, 0.5, 2.0, 0.1;
@@ -131,6 +169,7 @@ init_tunables! {
 
     // Futility pruning for qsearch.
     fp_qs_base: i32 = 353, 300, 400, 5;
+    fp_qs_see:  i32 = 1, -20, 30, 2;
 
     // Late move pruning.
     lmp_base:  i16 = 2, 2, 8, 1;
@@ -154,4 +193,7 @@ init_tunables! {
     // Qsearch beta cutoff lerps.
     qs_stand_pat_beta_lerp: f32 = 0.51089960, 0.2, 0.7, 0.05;
     qs_conservative_beta_lerp: f32 = 0.50773965, 0.2, 0.7, 0.05;
+
+    // Qsearch plies after which we always stand pat when not in check.
+    qs_max_ply: usize = 24, 8, 48, 2;
 }
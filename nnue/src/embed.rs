@@ -1,6 +1,6 @@
 use std::sync::OnceLock;
 
-use crate::arch::{NNUEData, QuantNNUEData};
+use crate::arch::{FEATURES, L1_LEN, L2_LEN, L3_LEN, NB_INPUT_BUCKETS, NB_OUTPUT_BUCKETS, NNUEData, QuantNNUEData};
 
 /// Raw NNUE data.
 #[cfg(all(feature = "embed", feature = "embed_direct"))]
@@ -9,6 +9,22 @@ pub static NNUE_EMBEDDED: NNUEData = unsafe { std::mem::transmute(*include_bytes
 #[cfg(all(feature = "embed", not(feature = "embed_direct")))]
 pub static NNUE_EMBEDDED: QuantNNUEData = unsafe { std::mem::transmute(*include_bytes!(env!("EVALFILE"))) };
 
+/// Size of the embedded network file, in bytes.
+#[cfg(feature = "embed_direct")]
+pub const NET_SIZE: usize = size_of::<NNUEData>();
+
+#[cfg(not(feature = "embed_direct"))]
+pub const NET_SIZE: usize = size_of::<QuantNNUEData>();
+
+/// Describe the network in use.
+pub fn net_info() -> String {
+    const MIB: usize = 1024 * 1024;
+    format!(
+        "NNUE evaluation using net {}MiB buckets {NB_INPUT_BUCKETS}x{NB_OUTPUT_BUCKETS} ({FEATURES}->{L1_LEN})x2->{L2_LEN}->{L3_LEN}->1",
+        NET_SIZE.div_ceil(MIB)
+    )
+}
+
 static PERMUTED_NNUE: OnceLock<Box<NNUEData>> = OnceLock::new();
 
 #[allow(unused_mut)]
@@ -1,14 +1,14 @@
 use chess::{
     defs::MAX_PLY,
-    types::{board::Board, color::Color, dirtypiece::DirtyPieces, eval::Eval},
+    types::{board::Board, color::Color, dirtypiece::DirtyPieces, eval::Eval, piece::Piece},
 };
 use utils::memory::boxed_zeroed;
 
 use crate::{
-    arch::{NNUEData, SCALE},
+    arch::{HalfAcc, NNUEData, SCALE},
     embed::get_permuted_nnue,
     inference::{
-        accumulator::{FullAcc, add1sub1, add1sub2, add2sub2},
+        accumulator::{FullAcc, add1_inplace, add1sub1, add1sub2, add2sub2},
         features::{king_changed, output_bucket},
         finny::FinnyTable,
         propagate::propagate_all_layers,
@@ -142,17 +142,42 @@ impl NNUE {
         }
     }
 
-    /// Evaluate the board using the NNUE.
-    pub fn evaluate(&mut self, b: &Board) -> Eval {
-        self.update_incremental(b);
+    /// Build the accumulator for one perspective from scratch, without using the stack or finny table.
+    fn fresh_acc(&self, b: &Board, perspective: Color) -> HalfAcc {
+        let ksq = b.ksq(perspective);
+        let mut acc = self.nn.ftb;
+
+        for c in Color::iter() {
+            for p in Piece::iter() {
+                for s in b.pc_bb(c, p) {
+                    add1_inplace(&mut acc, self.nn.feats_for(ksq, perspective, p, c, s));
+                }
+            }
+        }
+
+        acc
+    }
 
+    /// Check that the incrementally updated accumulator matches one built from scratch.
+    /// This does not touch the finny table, so a stale cache entry cannot hide a bad update.
+    #[cfg(debug_assertions)]
+    fn verify_accumulator(&self, b: &Board) {
+        for perspective in Color::iter() {
+            assert!(
+                self.fresh_acc(b, perspective) == self.stack[self.idx].feats[perspective.idx()],
+                "Accumulator for {perspective:?} does not match a full refresh: {}",
+                b.to_fen()
+            );
+        }
+    }
+
+    /// Run the accumulators for both perspectives through the rest of the network.
+    fn propagate(&self, b: &Board, feats: &[HalfAcc; Color::NUM]) -> Eval {
         let obkt = output_bucket(b.occ().nbits() as usize);
-        let acc = &self.stack[self.idx];
-        debug_assert!(acc.correct[0] && acc.correct[1]);
 
         let (stm, opp) = match b.stm {
-            Color::White => (&acc.feats[0], &acc.feats[1]),
-            Color::Black => (&acc.feats[1], &acc.feats[0]),
+            Color::White => (&feats[0], &feats[1]),
+            Color::Black => (&feats[1], &feats[0]),
         };
 
         let out = propagate_all_layers(self.nn, stm, opp, obkt);
@@ -160,4 +185,24 @@ impl NNUE {
         #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
         Eval((out * SCALE as f32) as i32)
     }
+
+    /// Evaluate the board using the NNUE.
+    pub fn evaluate(&mut self, b: &Board) -> Eval {
+        self.update_incremental(b);
+
+        #[cfg(debug_assertions)]
+        self.verify_accumulator(b);
+
+        let acc = &self.stack[self.idx];
+        debug_assert!(acc.correct[0] && acc.correct[1]);
+
+        self.propagate(b, &acc.feats)
+    }
+
+    /// Evaluate any board from scratch.
+    /// This leaves the accumulator stack alone, so it can be used for one-off evaluations.
+    pub fn evaluate_fresh(&self, b: &Board) -> Eval {
+        let feats = [self.fresh_acc(b, Color::White), self.fresh_acc(b, Color::Black)];
+        self.propagate(b, &feats)
+    }
 }